@@ -0,0 +1,146 @@
+//! Findings that are known and accepted, like one SSO password stored under several entries,
+//! read from a TOML file like:
+//!
+//! ```toml
+//! [[allow]]
+//! uuid = "8a4f0c3e-2b1d-4e5f-9a6b-7c8d9e0f1a2b"
+//! reason = "Okta SSO, the same credential is stored for every app that uses it"
+//!
+//! [[allow]]
+//! title = "Staging router"
+//! username = "admin"
+//! checks = ["weak"]
+//! reason = "Only reachable from the lab network"
+//! ```
+//!
+//! Each rule needs a reason. Rules without `checks` suppress duplicate, breached and weak
+//! findings. Suppressed findings aren't dropped, but listed separately in the report.
+use crate::Check;
+use crate::error::Error;
+use crate::report::{EntrySummary, Finding, Report};
+use crate::{Destination, write_to};
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AllowRule {
+    /// Matches the entry with this UUID. Takes precedence over title, username and URL.
+    pub uuid: Option<String>,
+    /// Matches entries with this title, username and URL. Any of them can be left out.
+    pub title: Option<String>,
+    pub username: Option<String>,
+    pub url: Option<String>,
+    /// The checks whose findings are suppressed. Defaults to duplicate, breached and weak.
+    pub checks: Option<Vec<Check>>,
+    /// Why these findings are acceptable. Required.
+    pub reason: String,
+}
+
+impl AllowRule {
+    fn allows(&self, finding: &Finding) -> bool {
+        let checks = match &self.checks {
+            Some(checks) => checks.as_slice(),
+            None => &[Check::Duplicate, Check::Breached, Check::Weak],
+        };
+        checks.contains(&finding.check) && self.matches(&finding.entry)
+    }
+
+    fn matches(&self, entry: &EntrySummary) -> bool {
+        if let Some(uuid) = &self.uuid {
+            // Entries from CSV exports have no UUID, and mustn't all match
+            return !entry.uuid.is_empty() && entry.uuid.eq_ignore_ascii_case(uuid);
+        }
+        [
+            (&self.title, &entry.title),
+            (&self.username, &entry.username),
+            (&self.url, &entry.url),
+        ]
+        .iter()
+        .all(|(expected, actual)| expected.as_ref().is_none_or(|expected| expected == *actual))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Allowlist {
+    #[serde(default)]
+    pub allow: Vec<AllowRule>,
+}
+
+impl Allowlist {
+    /// The reason given by the first rule that allows the finding, if any does
+    pub fn reason_for(&self, finding: &Finding) -> Option<&str> {
+        self.allow
+            .iter()
+            .find(|rule| rule.allows(finding))
+            .map(|rule| rule.reason.as_str())
+    }
+}
+
+pub fn read_allowlist(file_path: PathBuf) -> Result<Allowlist, Error> {
+    let contents = match fs::read_to_string(&file_path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            return Err(Error::Open {
+                path: file_path,
+                source: e,
+            });
+        }
+    };
+    let allowlist: Allowlist = toml::from_str(&contents)
+        .map_err(|e| Error::Parse(format!("{}: {}", file_path.display(), e)))?;
+    for (i, rule) in allowlist.allow.iter().enumerate() {
+        let problem = if rule.reason.trim().is_empty() {
+            Some("has no reason")
+        } else if rule.uuid.is_none()
+            && rule.title.is_none()
+            && rule.username.is_none()
+            && rule.url.is_none()
+        {
+            Some("needs a uuid, title, username or url to match entries by")
+        } else if [&rule.uuid, &rule.title, &rule.username, &rule.url]
+            .iter()
+            .any(|value| value.as_deref() == Some(""))
+        {
+            Some("has an empty uuid, title, username or url")
+        } else {
+            None
+        };
+        if let Some(problem) = problem {
+            return Err(Error::Parse(format!(
+                "{}: allow rule {} {}",
+                file_path.display(),
+                i + 1,
+                problem
+            )));
+        }
+    }
+    Ok(allowlist)
+}
+
+pub fn present_suppressed_findings(
+    report: &Report,
+    output_dest: &Destination,
+) -> Result<(), Error> {
+    if report.suppressed.is_empty() {
+        return Ok(());
+    }
+    write_to(
+        output_dest,
+        "\nThe following findings are allowed by your allowlist, and weren't counted:",
+    )?;
+    for suppressed in &report.suppressed {
+        write_to(
+            output_dest,
+            format!(
+                "   - {}: {} ({})",
+                suppressed.finding.entry,
+                crate::report::section_title(suppressed.finding.check).to_lowercase(),
+                suppressed.reason
+            ),
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,62 @@
+//! Words that must never appear in a password, like the company's name or product codenames,
+//! which zxcvbn has no way of knowing about. Matching ignores case and sees through common
+//! leetspeak, so `N0rthw1nd` contains `northwind`.
+use crate::context::read_context_words;
+use crate::error::Error;
+use std::path::PathBuf;
+
+/// Reads banned words, one per line, from a file. Blank lines are skipped.
+pub fn read_banned_words(file_path: PathBuf) -> Result<Vec<String>, Error> {
+    let mut banned_words: Vec<String> = read_context_words(file_path)?
+        .into_iter()
+        .map(|word| word.to_lowercase())
+        .collect();
+    banned_words.sort();
+    banned_words.dedup();
+    Ok(banned_words)
+}
+
+/// Letters a leetspeak character can stand for
+fn unleet(c: char) -> &'static [char] {
+    match c {
+        '0' => &['o'],
+        '1' => &['i', 'l'],
+        '2' => &['z'],
+        '3' => &['e'],
+        '4' | '@' => &['a'],
+        '5' | '$' => &['s'],
+        '6' | '9' => &['g'],
+        '7' | '+' => &['t'],
+        '8' => &['b'],
+        '!' | '|' => &['i', 'l'],
+        _ => &[],
+    }
+}
+
+fn matches_char(password_char: char, banned_char: char) -> bool {
+    password_char.to_lowercase().eq(banned_char.to_lowercase())
+        || unleet(password_char).contains(&banned_char)
+}
+
+/// Whether `word` (lowercase) appears anywhere in `password`, ignoring case and leetspeak
+pub(crate) fn contains_word(password: &str, word: &str) -> bool {
+    let password: Vec<char> = password.chars().collect();
+    let word: Vec<char> = word.chars().collect();
+    !word.is_empty()
+        && password.windows(word.len()).any(|window| {
+            window
+                .iter()
+                .zip(&word)
+                .all(|(&password_char, &banned_char)| matches_char(password_char, banned_char))
+        })
+}
+
+/// Returns the banned words that appear in `password`, in the order they were given.
+/// `banned_words` should already be lowercase, as [`read_banned_words`] returns them.
+pub fn banned_words_in(password: &str, banned_words: &[String]) -> Vec<String> {
+    banned_words
+        .iter()
+        .filter(|word| contains_word(password, word))
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,225 @@
+//! Words an attacker targeting a particular entry would try first, like the site's name or the
+//! user's email address. zxcvbn calls these "user inputs" and penalizes passwords built from
+//! them. The `--context` check also flags passwords that contain them outright, whatever
+//! zxcvbn thinks of them.
+use crate::banned::contains_word;
+use crate::entries::Entry;
+use crate::error::Error;
+use crate::report::Report;
+use crate::{Check, Destination, write_to};
+use std::fs;
+use std::path::PathBuf;
+
+/// Public suffixes with two labels, so that e.g. `bbc.co.uk` is registrable but `co.uk`
+/// isn't. Not the full Public Suffix List, but it covers the common cases.
+const TWO_LABEL_SUFFIXES: [&str; 24] = [
+    "co.uk", "org.uk", "ac.uk", "gov.uk", "me.uk", "ltd.uk", "plc.uk", "com.au", "net.au",
+    "org.au", "edu.au", "gov.au", "co.nz", "org.nz", "co.jp", "ne.jp", "or.jp", "com.br", "com.cn",
+    "com.mx", "co.in", "co.za", "com.tr", "co.kr",
+];
+
+/// Everything about an entry that might end up in its password: its title, username, URL's
+/// domain, group path and email address, plus any words the user gave with
+/// `--context-words`.
+pub fn user_inputs(entry: &Entry, context_words: &[String]) -> Vec<String> {
+    let mut inputs: Vec<String> = vec![entry.title.clone(), entry.username.clone()];
+    if let Some(host) = host(&entry.url) {
+        if let Some(domain) = registrable_domain(&host) {
+            inputs.extend(domain_parts(&domain));
+            inputs.push(domain);
+        }
+        inputs.push(host);
+    }
+    inputs.extend(entry.group.split('/').map(|group| group.to_string()));
+    if let Some((local_part, domain)) = entry.username.split_once('@') {
+        inputs.push(local_part.to_string());
+        inputs.extend(split_words(local_part));
+        if let Some(domain) = registrable_domain(domain) {
+            inputs.extend(domain_parts(&domain));
+        }
+    }
+    inputs.extend(context_words.iter().cloned());
+
+    let mut inputs: Vec<String> = inputs
+        .into_iter()
+        .map(|input| input.trim().to_lowercase())
+        // Very short inputs would match by chance
+        .filter(|input| input.chars().count() >= 3)
+        .collect();
+    inputs.sort();
+    inputs.dedup();
+    inputs
+}
+
+/// The host name in a URL, lowercased and without a leading "www.". Entries often have just
+/// a domain in their URL field, so the scheme is optional.
+pub fn host(url: &str) -> Option<String> {
+    let url = url.trim();
+    let without_scheme = match url.split_once("://") {
+        Some((_scheme, rest)) => rest,
+        None => url,
+    };
+    let authority = without_scheme
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default();
+    let host_and_port = match authority.rsplit_once('@') {
+        Some((_userinfo, host_and_port)) => host_and_port,
+        None => authority,
+    };
+    let host = host_and_port.split(':').next().unwrap_or_default();
+    let host = host.to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    if host.is_empty() || !host.contains('.') {
+        None
+    } else {
+        Some(host.to_string())
+    }
+}
+
+/// The part of a host name that someone actually registered, e.g. `google.co.uk` for
+/// `accounts.google.co.uk`. `None` for IP addresses and bare suffixes.
+pub fn registrable_domain(host: &str) -> Option<String> {
+    let host = host.trim_end_matches('.').to_lowercase();
+    if host.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+    let labels: Vec<&str> = host.split('.').filter(|label| !label.is_empty()).collect();
+    let suffix_labels = if labels.len() >= 2
+        && TWO_LABEL_SUFFIXES.contains(&labels[labels.len() - 2..].join(".").as_str())
+    {
+        2
+    } else {
+        1
+    };
+    if labels.len() <= suffix_labels {
+        return None;
+    }
+    Some(labels[labels.len() - suffix_labels - 1..].join("."))
+}
+
+/// The name in a registrable domain, and its hyphenated parts, e.g. `my-bank` and `bank`
+/// for `my-bank.co.uk`
+fn domain_parts(domain: &str) -> Vec<String> {
+    let name = domain.split('.').next().unwrap_or_default();
+    let mut parts = vec![name.to_string()];
+    parts.extend(split_words(name));
+    parts
+}
+
+/// Splits e.g. "jane.doe+shopping" into "jane", "doe" and "shopping"
+fn split_words(text: &str) -> Vec<String> {
+    text.split(['.', '_', '-', '+'])
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_string())
+        .collect()
+}
+
+/// Reads one word per line, skipping blank lines
+pub fn read_context_words(file_path: PathBuf) -> Result<Vec<String>, Error> {
+    match fs::read_to_string(&file_path) {
+        Ok(contents) => Ok(contents
+            .lines()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect()),
+        Err(e) => Err(Error::Open {
+            path: file_path,
+            source: e,
+        }),
+    }
+}
+
+/// The parts of an entry that are too obvious to put in its password, each with how to
+/// describe it: the username, title, the labels of the URL's host (without its public suffix)
+/// and, if the username is an email address, its local part
+fn entry_details(entry: &Entry) -> Vec<(&'static str, String)> {
+    let mut details = vec![("the username", entry.username.clone())];
+    if let Some((local_part, _domain)) = entry.username.split_once('@') {
+        details.push(("the email address's local part", local_part.to_string()));
+    }
+    details.push(("the title", entry.title.clone()));
+    if let Some(host) = host(&entry.url)
+        && let Some(domain) = registrable_domain(&host)
+        && let Some((_name, suffix)) = domain.split_once('.')
+        && let Some(labels) = host
+            .trim_end_matches('.')
+            .strip_suffix(&format!(".{}", suffix))
+    {
+        for label in labels.split('.') {
+            details.push(("part of the URL", label.to_string()));
+        }
+    }
+    details
+}
+
+/// Lowercases, and drops anything that isn't a letter or digit, so that a title like
+/// "Bank of America" is found in "bankofamerica1"
+fn squashed(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Every part of the entry (see `entry_details`) that its password contains, ignoring case,
+/// spaces and punctuation, and common leetspeak. Each is described in words, e.g.
+/// `contains the username "jane"`. Never includes the password.
+pub fn entry_details_in_password(entry: &Entry) -> Vec<String> {
+    let mut found: Vec<String> = vec![];
+    let mut found_values: Vec<String> = vec![];
+    for (description, value) in entry_details(entry) {
+        let lowercase = value.to_lowercase();
+        let squashed = squashed(&value);
+        // Anything shorter is too likely to turn up by chance
+        if squashed.chars().count() < 3
+            || found_values
+                .iter()
+                .any(|found_value| found_value.contains(&squashed))
+        {
+            continue;
+        }
+        if contains_word(&entry.pass, &lowercase) || contains_word(&entry.pass, &squashed) {
+            found.push(format!("contains {} \"{}\"", description, value));
+            found_values.push(squashed);
+        }
+    }
+    found
+}
+
+/// Finds entries whose passwords contain their own username, title, domain or email address
+pub fn find_entry_details_in_passwords(entries: &[Entry]) -> Vec<(Entry, Vec<String>)> {
+    entries
+        .iter()
+        .filter(|entry| !entry.pass.is_empty())
+        .map(|entry| (entry.clone(), entry_details_in_password(entry)))
+        .filter(|(_entry, found)| !found.is_empty())
+        .collect()
+}
+
+pub fn present_entry_details_in_passwords(
+    report: &Report,
+    output_dest: &Destination,
+) -> Result<(), Error> {
+    if report.count(Check::Context) == 0 {
+        return write_to(
+            output_dest,
+            "\nNone of your passwords contain their entry's username, title or website.",
+        );
+    }
+    write_to(
+        output_dest,
+        "\nThe following entries have passwords built from the entry's own details, which is the first thing an attacker would try:",
+    )?;
+    for finding in report.findings_for(Check::Context) {
+        write_to(
+            output_dest,
+            format!(
+                "   - {}: {}",
+                finding.entry,
+                finding.detail.as_deref().unwrap_or("")
+            ),
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,134 @@
+//! Comparing two reports, e.g. from before and after a round of password changes
+use crate::report::{
+    Finding, Report, check_name, escape_markdown_cell, findings_to_csv, is_same_entry,
+    section_title,
+};
+use crate::{Destination, Error, write_to};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Findings sorted by whether they were fixed, appeared, or are still there
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportDiff {
+    pub old_health_score: u8,
+    pub new_health_score: u8,
+    /// In the old report but not the new one
+    pub resolved: Vec<Finding>,
+    /// In the new report but not the old one
+    pub new: Vec<Finding>,
+    /// In both reports. These are taken from the new report.
+    pub unchanged: Vec<Finding>,
+}
+
+impl ReportDiff {
+    pub fn new(old: &Report, new: &Report) -> Self {
+        let (unchanged, new_findings): (Vec<Finding>, Vec<Finding>) = new
+            .findings
+            .iter()
+            .cloned()
+            .partition(|finding| old.findings.iter().any(|old| is_same_finding(old, finding)));
+        let resolved: Vec<Finding> = old
+            .findings
+            .iter()
+            .filter(|finding| !new.findings.iter().any(|new| is_same_finding(finding, new)))
+            .cloned()
+            .collect();
+        ReportDiff {
+            old_health_score: old.health_score(),
+            new_health_score: new.health_score(),
+            resolved,
+            new: new_findings,
+            unchanged,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// One row per finding, with a "Status" column saying which list it's in
+    pub fn to_csv(&self) -> Result<String, Error> {
+        findings_to_csv(
+            &["Status"],
+            self.sections()
+                .into_iter()
+                .flat_map(|(status, _, findings)| {
+                    findings
+                        .iter()
+                        .map(move |finding| (vec![status.to_string()], finding))
+                }),
+        )
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!(
+            "# Medic report diff\n\nHealth score went from {} to {}.\n",
+            self.old_health_score, self.new_health_score
+        );
+        for (_, title, findings) in self.sections() {
+            markdown.push_str(&format!("\n## {} ({})\n", title, findings.len()));
+            if findings.is_empty() {
+                continue;
+            }
+            markdown.push_str(
+                "\n| Check | Title | Username | URL | Group | Details |\n|---|---|---|---|---|---|\n",
+            );
+            for finding in findings {
+                let entry = &finding.entry;
+                markdown.push_str(&format!(
+                    "| {} | {} | {} | {} | {} | {} |\n",
+                    section_title(finding.check),
+                    escape_markdown_cell(&entry.title),
+                    escape_markdown_cell(&entry.username),
+                    escape_markdown_cell(&entry.url),
+                    escape_markdown_cell(&entry.group),
+                    escape_markdown_cell(&finding.describe()),
+                ));
+            }
+        }
+        markdown
+    }
+
+    fn sections(&self) -> [(&'static str, &'static str, &[Finding]); 3] {
+        [
+            ("resolved", "Resolved", &self.resolved),
+            ("new", "New", &self.new),
+            ("unchanged", "Unchanged", &self.unchanged),
+        ]
+    }
+}
+
+/// Details like duplicate group numbers or a password's age change from one run to the next,
+/// so findings are the same if they're from the same check on the same entry
+fn is_same_finding(a: &Finding, b: &Finding) -> bool {
+    a.check == b.check && is_same_entry(&a.entry, &b.entry)
+}
+
+/// Reads a report previously written with `--format json`
+pub fn read_report(path: PathBuf) -> Result<Report, Error> {
+    match fs::read_to_string(&path) {
+        Ok(json) => Report::from_json(&json),
+        Err(e) => Err(Error::Open { path, source: e }),
+    }
+}
+
+pub fn present_report_diff(diff: &ReportDiff, output_dest: &Destination) -> Result<(), Error> {
+    write_to(
+        output_dest,
+        format!(
+            "Health score went from {} to {}.",
+            diff.old_health_score, diff.new_health_score
+        ),
+    )?;
+    for (_, title, findings) in diff.sections() {
+        write_to(output_dest, format!("\n{} ({}):", title, findings.len()))?;
+        for finding in findings {
+            write_to(
+                output_dest,
+                format!("   - {} ({})", finding.entry, check_name(finding.check)),
+            )?;
+        }
+    }
+    Ok(())
+}
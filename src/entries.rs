@@ -1,30 +1,57 @@
+extern crate chrono;
 extern crate keepass;
+use crate::error::Error;
+use crate::report::EntrySummary;
+use chrono::NaiveDateTime;
 use keepass::Database;
 use keepass::DatabaseKey;
-use keepass::db::NodeRef;
-use keepass::error::DatabaseOpenError;
+use keepass::db::{Group, Node, NodeRef};
+use std::collections::HashMap;
 use std::fs::File;
 // use std::io::prelude::Read;
+use std::path::Path;
 use std::path::PathBuf;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Entry {
     pub title: String,
     pub url: String,
     pub username: String,
     pub pass: String,
     pub digest: String,
+    /// KeePass entry UUID. Empty when the source (e.g. a CSV export) doesn't provide one.
+    pub uuid: String,
+    pub created: Option<NaiveDateTime>,
+    pub modified: Option<NaiveDateTime>,
+    pub accessed: Option<NaiveDateTime>,
+    /// Whether the "expires" box is ticked for this entry in KeePass
+    pub expires: bool,
+    /// Only meaningful when `expires` is true
+    pub expiry: Option<NaiveDateTime>,
+    /// Previous versions of this entry from KeePass's history, oldest first
+    pub history: Vec<Entry>,
+    pub notes: String,
+    /// Any string fields beyond KeePass's standard ones, as (name, value) pairs sorted by name
+    pub custom_fields: Vec<(String, String)>,
+    /// When this `Entry` stands in for one of an entry's custom fields rather than its
+    /// password, the name of that field
+    pub field: Option<String>,
+    pub tags: Vec<String>,
+    /// Path of the database this entry was read from, as given on the command line. Not just
+    /// the file name, since e.g. `alice/passwords.kdbx` and `bob/passwords.kdbx` are different
+    /// databases.
+    pub source: String,
+    /// Path of the group this entry is in, like "Root/Banking"
+    pub group: String,
+    /// Line of the CSV export this entry was read from, which tells apart entries that
+    /// otherwise look the same. `None` for entries from a KeePass database, which have a UUID.
+    pub line: Option<u64>,
 }
 
+// Entries are described the same way in reports as anywhere else
 impl std::fmt::Display for Entry {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        if !self.title.is_empty() {
-            write!(f, "{} on {}", self.username, self.title)
-        } else if self.title.is_empty() && !self.url.is_empty() {
-            write!(f, "{} for {}", self.username, self.url)
-        } else {
-            write!(f, "{}", self.username)
-        }
+        EntrySummary::from(self).fmt(f)
     }
 }
 
@@ -32,49 +59,129 @@ fn unlock_keepass_database(
     path: PathBuf,
     db_pass: String,
     keyfile_path: Option<PathBuf>,
-) -> Result<keepass::Database, DatabaseOpenError> {
-    let mut db_file = match File::open(path) {
+) -> Result<keepass::Database, Error> {
+    let mut db_file = match File::open(&path) {
         Ok(db) => db,
-        Err(e) => panic!("Error opening KeePass database file: {}", e),
-    };
-
-    let keyfile: Option<File> = match keyfile_path {
-        Some(keyfile_path) => match File::open(keyfile_path) {
-            Ok(keyfile) => Some(keyfile),
-            Err(e) => panic!("Error opening specified keyfile: {}", e),
-        },
-        None => None,
+        Err(e) => return Err(Error::Open { path, source: e }),
     };
 
     // The key is both the password and the keyfile
     // https://docs.rs/keepass/latest/keepass/struct.DatabaseKey.html#method.with_keyfile
-    let key = match keyfile {
-        Some(mut keyfile) => DatabaseKey::new()
-            .with_password(&db_pass)
-            .with_keyfile(&mut keyfile),
-        None => Ok(DatabaseKey::new().with_password(&db_pass)),
+    let key = match keyfile_path {
+        Some(keyfile_path) => {
+            let key = File::open(&keyfile_path).and_then(|mut keyfile| {
+                DatabaseKey::new()
+                    .with_password(&db_pass)
+                    .with_keyfile(&mut keyfile)
+            });
+            match key {
+                Ok(key) => key,
+                Err(e) => {
+                    return Err(Error::Open {
+                        path: keyfile_path,
+                        source: e,
+                    });
+                }
+            }
+        }
+        None => DatabaseKey::new().with_password(&db_pass),
     };
-    Database::open(&mut db_file, key?)
+    Ok(Database::open(&mut db_file, key)?)
+}
+
+fn build_entry_from_keepass_entry(
+    e: &keepass::db::Entry,
+    entry_password: &str,
+    source: &str,
+) -> Entry {
+    Entry {
+        title: e.get_title().unwrap_or("").to_string(),
+        username: e.get_username().unwrap_or("").to_string(),
+        // url: e.get("URL").unwrap().to_string(),
+        url: e.get_url().unwrap_or("").to_string(),
+        // pass: e.get_password().unwrap().to_string(),
+        pass: entry_password.to_string(),
+        digest: sha1_smol::Sha1::from(entry_password)
+            .digest()
+            .to_string()
+            .to_uppercase(),
+        uuid: e.uuid.to_string(),
+        created: e.times.get_creation().copied(),
+        modified: e.times.get_last_modification().copied(),
+        accessed: e.times.get_last_access().copied(),
+        expires: e.times.expires,
+        expiry: e.get_expiry_time().copied(),
+        history: vec![],
+        notes: e.get("Notes").unwrap_or("").to_string(),
+        custom_fields: read_custom_fields(e),
+        field: None,
+        tags: e.tags.clone(),
+        source: source.to_string(),
+        group: String::new(),
+        line: None,
+    }
+}
+
+const STANDARD_FIELDS: [&str; 5] = ["Title", "UserName", "Password", "URL", "Notes"];
+
+fn read_custom_fields(e: &keepass::db::Entry) -> Vec<(String, String)> {
+    let mut custom_fields: Vec<(String, String)> = e
+        .fields
+        .keys()
+        .filter(|name| !STANDARD_FIELDS.contains(&name.as_str()))
+        .filter_map(|name| {
+            e.get(name)
+                .map(|value| (name.to_string(), value.to_string()))
+        })
+        .collect();
+    custom_fields.sort();
+    custom_fields
+}
+
+/// Records the path of the group each entry is in, keyed by entry UUID
+fn find_group_paths(group: &Group, path: &str, group_paths: &mut HashMap<String, String>) {
+    for node in &group.children {
+        match node {
+            Node::Group(child) => {
+                find_group_paths(child, &format!("{}/{}", path, child.name), group_paths)
+            }
+            Node::Entry(e) => {
+                group_paths.insert(e.uuid.to_string(), path.to_string());
+            }
+        }
+    }
+}
+
+/// What was read from a database
+#[derive(Debug, Clone, Default)]
+pub struct DatabaseEntries {
+    pub entries: Vec<Entry>,
+    /// Entries whose password couldn't be read, so they couldn't be checked. Not in `entries`.
+    pub unreadable: Vec<Entry>,
 }
 
 pub fn build_entries_from_keepass_db(
     file_path: PathBuf,
     db_pass: String,
     keyfile_path: Option<PathBuf>,
-) -> Option<Vec<Entry>> {
+) -> Result<Vec<Entry>, Error> {
+    Ok(read_keepass_db(file_path, db_pass, keyfile_path)?.entries)
+}
+
+/// Like [`build_entries_from_keepass_db`], but also returns the entries whose password
+/// couldn't be read
+pub fn read_keepass_db(
+    file_path: PathBuf,
+    db_pass: String,
+    keyfile_path: Option<PathBuf>,
+) -> Result<DatabaseEntries, Error> {
     let mut entries: Vec<Entry> = vec![];
+    let mut unreadable: Vec<Entry> = vec![];
+    let source = source_name(&file_path);
 
-    println!("Attempting to unlock your KeePass database...");
-    let db = match unlock_keepass_database(file_path, db_pass, keyfile_path) {
-        Ok(db) => db,
-        Err(e) => {
-            eprintln!(
-                "Error unlocking KeePass database: {}. No entries found. Aborting.",
-                e
-            );
-            return None;
-        }
-    };
+    let db = unlock_keepass_database(file_path, db_pass, keyfile_path)?;
+    let mut group_paths: HashMap<String, String> = HashMap::new();
+    find_group_paths(&db.root, &db.root.name, &mut group_paths);
 
     // Iterate over all Groups and Nodes
     for node in &db.root {
@@ -86,62 +193,68 @@ pub fn build_entries_from_keepass_db(
                 let entry_password: &str = match e.get_password() {
                     Some(p) => p,
                     None => {
-                        eprintln!(
-                            "Error reading a password for entry titled: \"{}\", username: \"{}\", on site {}.",
-                            e.get_title().unwrap_or("Unknown"),
-                            e.get_username().unwrap_or("Unknown"),
-                            e.get("URL").unwrap_or("Unknown"),
-                        );
-                        // return None;
+                        unreadable.push(build_entry_from_keepass_entry(e, "", &source));
                         continue;
                     }
                 };
 
-                let this_entry = Entry {
-                    title: e.get_title().unwrap_or("").to_string(),
-                    username: e.get_username().unwrap_or("").to_string(),
-                    // url: e.get("URL").unwrap().to_string(),
-                    url: e.get_url().unwrap_or("").to_string(),
-                    // pass: e.get_password().unwrap().to_string(),
-                    pass: entry_password.to_string(),
-                    digest: sha1_smol::Sha1::from(entry_password)
-                        .digest()
-                        .to_string()
-                        .to_uppercase(),
-                };
+                let mut this_entry = build_entry_from_keepass_entry(e, entry_password, &source);
+                this_entry.group = group_paths
+                    .get(&e.uuid.to_string())
+                    .cloned()
+                    .unwrap_or_default();
+                if let Some(history) = &e.history {
+                    this_entry.history = history
+                        .get_entries()
+                        .iter()
+                        .filter_map(|old_version| match old_version.get_password() {
+                            Some(p) if !p.is_empty() => {
+                                Some(build_entry_from_keepass_entry(old_version, p, &source))
+                            }
+                            _ => None,
+                        })
+                        .collect();
+                    this_entry
+                        .history
+                        .sort_by_key(|old_version| old_version.modified);
+                }
                 if !this_entry.pass.is_empty() {
                     entries.push(this_entry);
                 }
             }
         }
     }
-    println!("Successfully read KeePass database!");
-    Some(entries)
+    Ok(DatabaseEntries {
+        entries,
+        unreadable,
+    })
 }
 
-pub fn build_entries_from_csv(file_path: PathBuf) -> Option<Vec<Entry>> {
+pub fn build_entries_from_csv(file_path: PathBuf) -> Result<Vec<Entry>, Error> {
     let mut entries: Vec<Entry> = vec![];
+    let source = source_name(&file_path);
 
-    let file = match File::open(file_path) {
+    let file = match File::open(&file_path) {
         Ok(f) => f,
         Err(e) => {
-            eprintln!("Error reading CSV file: {}. Aborting.", e);
-            return None;
+            return Err(Error::Open {
+                path: file_path,
+                source: e,
+            });
         }
     };
     let mut rdr = csv::Reader::from_reader(file);
+    // KeePassXC exports include "Last Modified", "Created" and (more recently) "Tags" columns;
+    // older exports don't, so look them up by name rather than by position
+    let headers = rdr.headers()?;
+    let (modified_column, created_column, tags_column) = (
+        headers.iter().position(|h| h == "Last Modified"),
+        headers.iter().position(|h| h == "Created"),
+        headers.iter().position(|h| h == "Tags"),
+    );
     // Loop over each record.
     for result in rdr.records() {
-        let record = match result {
-            Ok(rec) => rec,
-            Err(e) => {
-                eprintln!(
-                    "Error reading a line of the specified CSV file: {}. Aborting.",
-                    e
-                );
-                return None;
-            }
-        };
+        let record = result?;
 
         if record.get(0) == Some("Group") && record.get(1) == Some("Title") {
             continue;
@@ -150,28 +263,69 @@ pub fn build_entries_from_csv(file_path: PathBuf) -> Option<Vec<Entry>> {
         let entry_password: &str = match record.get(3) {
             Some(p) => p,
             None => {
-                println!(
-                    "Error reading a password for entry:\n{}, username {}, on site {}.\nAborting without finding any entries.",
+                return Err(Error::Parse(format!(
+                    "Couldn't read a password for entry: {}, username {}, on site {}.",
                     record.get(1).unwrap_or("Unknown Title"),
                     record.get(2).unwrap_or("Unknown"),
                     record.get(4).unwrap_or("Unknown URL"),
-                );
-                return None;
+                )));
             }
         };
         let this_entry = Entry {
-            title: record.get(1).unwrap().to_string(),
-            username: record.get(2).unwrap().to_string(),
-            url: record.get(4).unwrap().to_string(),
+            title: record.get(1).unwrap_or("").to_string(),
+            username: record.get(2).unwrap_or("").to_string(),
+            url: record.get(4).unwrap_or("").to_string(),
             pass: entry_password.to_string(),
+            notes: record.get(5).unwrap_or("").to_string(),
+            group: record.get(0).unwrap_or("").to_string(),
             digest: sha1_smol::Sha1::from(entry_password)
                 .digest()
                 .to_string()
                 .to_uppercase(),
+            created: created_column
+                .and_then(|i| record.get(i))
+                .and_then(parse_csv_timestamp),
+            modified: modified_column
+                .and_then(|i| record.get(i))
+                .and_then(parse_csv_timestamp),
+            tags: tags_column
+                .and_then(|i| record.get(i))
+                .map(split_tags)
+                .unwrap_or_default(),
+            source: source.clone(),
+            line: record.position().map(|position| position.line()),
+            ..Default::default()
         };
         if !this_entry.pass.is_empty() {
             entries.push(this_entry);
         }
     }
-    Some(entries)
+    Ok(entries)
+}
+
+fn source_name(file_path: &Path) -> String {
+    file_path.display().to_string()
+}
+
+/// KeePass separates tags with semicolons, KeePassXC with commas
+fn split_tags(tags: &str) -> Vec<String> {
+    tags.split([';', ','])
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+/// KeePassXC writes timestamps as ISO 8601 in UTC, like `2023-05-01T12:34:56Z`.
+/// KeePass itself stores UTC, so we keep everything as naive UTC times.
+fn parse_csv_timestamp(timestamp: &str) -> Option<NaiveDateTime> {
+    let timestamp = timestamp.trim();
+    if timestamp.is_empty() {
+        return None;
+    }
+    match chrono::DateTime::parse_from_rfc3339(timestamp) {
+        Ok(datetime) => Some(datetime.naive_utc()),
+        Err(_e) => NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%dT%H:%M:%S")
+            .or_else(|_e| NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S"))
+            .ok(),
+    }
 }
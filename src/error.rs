@@ -0,0 +1,96 @@
+use keepass::error::DatabaseOpenError;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Everything that can go wrong while reading a database or checking its passwords
+#[derive(Debug)]
+pub enum Error {
+    /// A database, key file or password list couldn't be opened or read
+    Open { path: PathBuf, source: io::Error },
+    /// The file to check isn't a kdbx or CSV file
+    UnsupportedFileType(PathBuf),
+    /// The KeePass database couldn't be unlocked, e.g. because of a wrong password or key file,
+    /// or an unsupported KDF
+    Unlock(DatabaseOpenError),
+    /// A CSV export or other input couldn't be parsed
+    Parse(String),
+    /// A request to the HaveIBeenPwned API failed
+    Network(reqwest::Error),
+    /// zxcvbn couldn't estimate the strength of a password
+    Estimate(zxcvbn::ZxcvbnError),
+    /// Reading from the terminal or writing the report failed
+    Io(io::Error),
+    /// The user declined to go on, e.g. when asked whether to overwrite an existing report
+    Aborted,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Open { path, source } => {
+                write!(f, "Error opening {}: {}", path.display(), source)
+            }
+            Error::UnsupportedFileType(path) => write!(
+                f,
+                "{} doesn't look like a KeePass database. Expected a .kdbx or .csv file.",
+                path.display()
+            ),
+            Error::Unlock(e) => write!(f, "Error unlocking KeePass database: {}", e),
+            Error::Parse(message) => write!(f, "Error parsing input: {}", message),
+            Error::Network(e) => write!(f, "Error contacting HaveIBeenPwned: {}", e),
+            Error::Estimate(e) => write!(f, "Error getting password strength estimate: {}", e),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Aborted => write!(f, "OK, exiting"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Open { source, .. } => Some(source),
+            Error::Unlock(e) => Some(e),
+            Error::Network(e) => Some(e),
+            Error::Estimate(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::UnsupportedFileType(_) | Error::Parse(_) | Error::Aborted => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<DatabaseOpenError> for Error {
+    fn from(e: DatabaseOpenError) -> Self {
+        Error::Unlock(e)
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Network(e)
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(e: csv::Error) -> Self {
+        Error::Parse(e.to_string())
+    }
+}
+
+impl From<zxcvbn::ZxcvbnError> for Error {
+    fn from(e: zxcvbn::ZxcvbnError) -> Self {
+        Error::Estimate(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Parse(e.to_string())
+    }
+}
@@ -0,0 +1,111 @@
+use crate::entries::Entry;
+use crate::error::Error;
+use crate::report::{Finding, Report, is_same_entry};
+use crate::{Check, Destination, make_digest_map, write_to};
+
+/// Returns every password each entry has had in the past, as copies of the current entry with
+/// the old password (and the time it was last in use) swapped in. Consecutive history items
+/// that share a password are collapsed, and an entry's current password is only included if
+/// the entry changed away from it at some point.
+pub fn previous_passwords(entries: &[Entry]) -> Vec<Entry> {
+    let mut previous_passwords: Vec<Entry> = vec![];
+    for entry in entries {
+        let mut versions = entry.history.iter().peekable();
+        while let Some(old_version) = versions.next() {
+            let next_digest = match versions.peek() {
+                Some(next_version) => &next_version.digest,
+                None => &entry.digest,
+            };
+            if &old_version.digest != next_digest {
+                previous_passwords.push(Entry {
+                    pass: old_version.pass.clone(),
+                    digest: old_version.digest.clone(),
+                    modified: old_version.modified,
+                    history: vec![],
+                    ..entry.clone()
+                });
+            }
+        }
+    }
+    previous_passwords
+}
+
+/// Finds entries whose current password was previously used, either by the same entry (i.e.
+/// the password was "rotated" back to an old one) or by another entry. Returns each such entry
+/// along with the entries that used to have its password.
+pub fn find_reused_previous_passwords(entries: &[Entry]) -> Vec<(Entry, Vec<Entry>)> {
+    let previous_passwords = previous_passwords(entries);
+    let history_digest_map = make_digest_map(&previous_passwords).unwrap_or_default();
+
+    let mut reused_entries = vec![];
+    for entry in entries {
+        if let Some(previous_owners) = history_digest_map.get(&entry.digest) {
+            let mut previous_owners = previous_owners.clone();
+            previous_owners.dedup_by(|a, b| is_same_entry(a, b));
+            reused_entries.push((entry.clone(), previous_owners));
+        }
+    }
+    reused_entries
+}
+
+pub fn present_reused_previous_passwords(
+    report: &Report,
+    output_dest: &Destination,
+) -> Result<(), Error> {
+    let reused: Vec<&Finding> = report
+        .findings_for(Check::History)
+        .filter(|finding| !finding.previously_used_by.is_empty())
+        .collect();
+    if reused.is_empty() {
+        return write_to(
+            output_dest,
+            "\nNone of your current passwords were used previously.",
+        );
+    }
+    write_to(
+        output_dest,
+        "\nThe following entries use a password that was used before:",
+    )?;
+    for finding in reused {
+        let entry = &finding.entry;
+        write_to(output_dest, format!("   - {}, previously used by:", entry))?;
+        for previous_owner in &finding.previously_used_by {
+            if is_same_entry(previous_owner, entry) {
+                write_to(output_dest, "      - this same entry")?;
+            } else {
+                write_to(output_dest, format!("      - {}", previous_owner))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn present_breached_previous_passwords(
+    report: &Report,
+    output_dest: &Destination,
+) -> Result<(), Error> {
+    let breached: Vec<&Finding> = report
+        .findings_for(Check::History)
+        .filter(|finding| finding.previously_used_by.is_empty())
+        .collect();
+    if breached.is_empty() {
+        return write_to(
+            output_dest,
+            "I didn't find any of your previous passwords on the breached passwords list",
+        );
+    }
+    write_to(
+        output_dest,
+        "The following entries used to have passwords contained in the list of breached passwords:",
+    )?;
+    for finding in breached {
+        match finding.date {
+            Some(modified) => write_to(
+                output_dest,
+                format!("   - {} (password from {})", finding.entry, modified.date()),
+            )?,
+            None => write_to(output_dest, format!("   - {}", finding.entry))?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,278 @@
+//! A single-file HTML dashboard, in the spirit of 1Password's Watchtower. Everything (styles and
+//! the table sorting script) is inline, so the report can be opened offline and shared as is.
+use crate::Check;
+use crate::report::{Finding, Report};
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; margin: 2em auto; max-width: 72em; padding: 0 1em; color: #222; }
+h1 { margin-bottom: 0.2em; }
+.meta { color: #666; }
+.cards { display: flex; flex-wrap: wrap; gap: 1em; margin: 1.5em 0; }
+.card { border: 1px solid #ddd; border-radius: 8px; padding: 1em 1.5em; min-width: 8em; }
+.card .number { font-size: 2em; font-weight: bold; }
+.good { color: #1a7f37; }
+.fair { color: #9a6700; }
+.poor { color: #cf222e; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 2em; }
+th, td { border-bottom: 1px solid #eee; padding: 0.4em 0.6em; text-align: left; vertical-align: top; }
+th { background: #f6f8fa; cursor: pointer; user-select: none; }
+th::after { content: " \2195"; color: #aaa; }
+.priority { color: #cf222e; font-weight: bold; }
+ul { margin: 0; padding-left: 1.2em; }
+"#;
+
+// Sorts a table by the clicked column, toggling between ascending and descending. Cells with a
+// data-sort attribute sort by that instead of their text, so numbers sort as numbers.
+const SCRIPT: &str = r#"
+document.querySelectorAll("table.sortable th").forEach(function (th) {
+  th.addEventListener("click", function () {
+    var table = th.closest("table");
+    var tbody = table.tBodies[0];
+    var ascending = th.dataset.order !== "asc";
+    table.querySelectorAll("th").forEach(function (other) { delete other.dataset.order; });
+    th.dataset.order = ascending ? "asc" : "desc";
+    var key = function (row) {
+      var cell = row.cells[th.cellIndex];
+      return cell.dataset.sort !== undefined ? parseFloat(cell.dataset.sort) : cell.textContent.toLowerCase();
+    };
+    Array.from(tbody.rows)
+      .sort(function (a, b) {
+        var x = key(a), y = key(b);
+        return (x < y ? -1 : x > y ? 1 : 0) * (ascending ? 1 : -1);
+      })
+      .forEach(function (row) { tbody.appendChild(row); });
+  });
+});
+"#;
+
+pub fn render(report: &Report) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>Medic report</title>\n");
+    html.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", STYLE));
+    html.push_str("<h1>Medic report</h1>\n");
+    html.push_str(&format!(
+        "<p class=\"meta\">medic {}, {} entries checked</p>\n",
+        escape(&report.medic_version),
+        report.entries_checked
+    ));
+    html.push_str(&summary_cards(report));
+    if report.findings.is_empty() {
+        html.push_str("<p class=\"good\">No problems found.</p>\n");
+    } else {
+        html.push_str(&group_breakdown(report));
+        for check in Check::value_variants() {
+            let findings: Vec<&Finding> = report.findings_for(*check).collect();
+            if !findings.is_empty() {
+                html.push_str(&findings_table(*check, &findings));
+            }
+        }
+    }
+    if !report.suppressed.is_empty() {
+        html.push_str(&suppressed_table(report));
+    }
+    html.push_str(&format!("<script>{}</script>\n</body>\n</html>", SCRIPT));
+    html
+}
+
+fn summary_cards(report: &Report) -> String {
+    let health_score = report.health_score();
+    let class = match health_score {
+        80..=100 => "good",
+        50..=79 => "fair",
+        _ => "poor",
+    };
+    let mut cards = format!(
+        "<div class=\"cards\">\n<div class=\"card\"><div class=\"number {}\">{}</div>Health score</div>\n",
+        class, health_score
+    );
+    for check in Check::value_variants() {
+        let count = report.count(*check);
+        if count > 0 {
+            cards.push_str(&format!(
+                "<div class=\"card\"><div class=\"number\">{}</div>{}</div>\n",
+                count,
+                crate::report::section_title(*check)
+            ));
+        }
+    }
+    cards.push_str("</div>\n");
+    cards
+}
+
+/// How many findings of each kind are in each KeePass group
+fn group_breakdown(report: &Report) -> String {
+    let checks: Vec<Check> = Check::value_variants()
+        .iter()
+        .copied()
+        .filter(|check| report.count(*check) > 0)
+        .collect();
+    let mut counts: BTreeMap<&str, BTreeMap<usize, usize>> = BTreeMap::new();
+    for finding in &report.findings {
+        let column = checks
+            .iter()
+            .position(|check| *check == finding.check)
+            .unwrap_or_default();
+        *counts
+            .entry(finding.entry.group.as_str())
+            .or_default()
+            .entry(column)
+            .or_default() += 1;
+    }
+
+    let mut table =
+        String::from("<h2>By group</h2>\n<table class=\"sortable\">\n<thead><tr><th>Group</th>");
+    for check in &checks {
+        table.push_str(&format!(
+            "<th>{}</th>",
+            crate::report::section_title(*check)
+        ));
+    }
+    table.push_str("</tr></thead>\n<tbody>\n");
+    for (group, group_counts) in counts {
+        let group = if group.is_empty() { "(none)" } else { group };
+        table.push_str(&format!("<tr><td>{}</td>", escape(group)));
+        for column in 0..checks.len() {
+            let count = group_counts.get(&column).copied().unwrap_or_default();
+            table.push_str(&format!("<td data-sort=\"{0}\">{0}</td>", count));
+        }
+        table.push_str("</tr>\n");
+    }
+    table.push_str("</tbody>\n</table>\n");
+    table
+}
+
+fn suppressed_table(report: &Report) -> String {
+    let mut table = format!(
+        "<h2>Allowed findings ({})</h2>\n<table class=\"sortable\">\n<thead><tr><th>Check</th><th>Title</th><th>Username</th><th>URL</th><th>Reason</th></tr></thead>\n<tbody>\n",
+        report.suppressed.len()
+    );
+    for suppressed in &report.suppressed {
+        let entry = &suppressed.finding.entry;
+        table.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            crate::report::section_title(suppressed.finding.check),
+            escape(&entry.title),
+            escape(&entry.username),
+            escape(&entry.url),
+            escape(&suppressed.reason)
+        ));
+    }
+    table.push_str("</tbody>\n</table>\n");
+    table
+}
+
+fn findings_table(check: Check, findings: &[&Finding]) -> String {
+    let extra_headings: &[&str] = match check {
+        Check::Weak => &["Score", "Time to guess offline", "Warning", "Suggestions"],
+        Check::Duplicate | Check::Similar => &["Shared with", "Entries"],
+        Check::Pattern => &["Family", "Entries", "Pattern"],
+        _ => &["Details"],
+    };
+    let mut table = format!(
+        "<h2>{} ({})</h2>\n<table class=\"sortable\">\n<thead><tr><th>Title</th><th>Username</th><th>URL</th><th>Group</th>",
+        crate::report::section_title(check),
+        findings.len()
+    );
+    for heading in extra_headings {
+        table.push_str(&format!("<th>{}</th>", heading));
+    }
+    table.push_str("</tr></thead>\n<tbody>\n");
+    for finding in findings {
+        let entry = &finding.entry;
+        let title = match &entry.field {
+            Some(field) => format!("{} ({} field)", entry.title, field),
+            None => entry.title.clone(),
+        };
+        table.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td>",
+            escape(&title),
+            escape(&entry.username),
+            escape(&entry.url),
+            escape(&entry.group)
+        ));
+        match check {
+            Check::Weak => {
+                let score = finding.score.unwrap_or_default();
+                let (crack_seconds, crack_time) = match &finding.crack_times {
+                    Some(crack_times) => (
+                        crack_times.offline_fast_hash_seconds,
+                        crack_times.offline_fast_hash.as_str(),
+                    ),
+                    None => (0, ""),
+                };
+                // Banned words aren't something zxcvbn warns about, so they go first
+                let warning: Vec<&str> = finding
+                    .detail
+                    .iter()
+                    .chain(&finding.warning)
+                    .map(String::as_str)
+                    .collect();
+                table.push_str(&format!(
+                    "<td data-sort=\"{0}\">{0}/4</td><td data-sort=\"{1}\">{2}</td><td>{3}</td><td>{4}</td>",
+                    score,
+                    crack_seconds,
+                    escape(crack_time),
+                    escape(&warning.join(". ")),
+                    list(&finding.suggestions)
+                ));
+            }
+            Check::Duplicate | Check::Similar => {
+                let same_account = match (finding.same_account, &finding.detail) {
+                    (true, Some(detail)) => format!(" ({})", escape(detail)),
+                    _ => String::new(),
+                };
+                table.push_str(&format!(
+                    "<td data-sort=\"{0}\">group {0}{2}</td><td data-sort=\"{1}\">{1}</td>",
+                    finding.duplicate_group.unwrap_or_default(),
+                    finding.count.unwrap_or_default(),
+                    same_account
+                ));
+            }
+            Check::Pattern => {
+                table.push_str(&format!(
+                    "<td data-sort=\"{0}\">family {0}</td><td data-sort=\"{1}\">{1}</td><td>{2}</td>",
+                    finding.duplicate_group.unwrap_or_default(),
+                    finding.count.unwrap_or_default(),
+                    escape(finding.detail.as_deref().unwrap_or(""))
+                ));
+            }
+            _ => {
+                let priority = if finding.high_priority {
+                    "<span class=\"priority\">HIGH PRIORITY</span> "
+                } else {
+                    ""
+                };
+                table.push_str(&format!(
+                    "<td>{}{}</td>",
+                    priority,
+                    escape(finding.detail.as_deref().unwrap_or(""))
+                ));
+            }
+        }
+        table.push_str("</tr>\n");
+    }
+    table.push_str("</tbody>\n</table>\n");
+    table
+}
+
+fn list(items: &[String]) -> String {
+    if items.is_empty() {
+        return String::new();
+    }
+    let items: Vec<String> = items
+        .iter()
+        .map(|item| format!("<li>{}</li>", escape(item)))
+        .collect();
+    format!("<ul>{}</ul>", items.concat())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -1,17 +1,36 @@
+extern crate chrono;
 extern crate csv;
 extern crate indicatif;
 extern crate keepass;
 extern crate reqwest;
 extern crate rpassword;
+extern crate serde;
+extern crate serde_json;
 extern crate sha1_smol;
 extern crate zxcvbn;
 
+pub mod allowlist;
+pub mod banned;
+pub mod context;
+pub mod diff;
 pub mod entries;
+pub mod error;
+pub mod history;
+pub mod html;
+pub mod patterns;
+pub mod policy;
+pub mod report;
+pub mod secrets;
+pub mod similar;
+pub mod tags;
 use crate::entries::Entry;
+pub use crate::error::Error;
+pub use crate::report::Report;
+use crate::report::{EntryIdentity, EntrySummary, Finding};
 use entries::build_entries_from_csv;
-use entries::build_entries_from_keepass_db;
+use entries::{DatabaseEntries, read_keepass_db};
 use indicatif::{ProgressBar, ProgressStyle};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::ffi::OsStr;
 use std::fs::File;
 use std::fs::OpenOptions;
@@ -33,36 +52,73 @@ pub enum VisibilityPreference {
     Show,
     Hide,
 }
+/// The kinds of checks medic can run
+#[derive(
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Clone,
+    Copy,
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Check {
+    Breached,
+    Weak,
+    Context,
+    Policy,
+    Duplicate,
+    Similar,
+    Pattern,
+    Stale,
+    Expired,
+    History,
+    Notes,
+}
 #[derive(Debug, PartialEq, Clone)]
 pub enum BreachedPasswordState {
     Sha1,
     Clear,
 }
 
-pub fn get_entries(file_path: PathBuf, keyfile_path: Option<PathBuf>) -> Option<Vec<Entry>> {
+pub fn get_entries(file_path: PathBuf, keyfile_path: Option<PathBuf>) -> Result<Vec<Entry>, Error> {
+    Ok(get_entries_with_password(file_path, keyfile_path, None)?.entries)
+}
+
+/// Like [`get_entries`], but unlocks a KeePass database with `db_pass` if it's given, rather
+/// than asking for its password, and also returns the entries whose password couldn't be read
+pub fn get_entries_with_password(
+    file_path: PathBuf,
+    keyfile_path: Option<PathBuf>,
+    db_pass: Option<String>,
+) -> Result<DatabaseEntries, Error> {
     let file_extension = match get_file_extension(&file_path) {
         Some(extension) => extension,
-        None => {
-            eprintln!("No file extension found");
-            return None;
-        }
+        None => return Err(Error::UnsupportedFileType(file_path)),
     };
 
     match file_extension.as_str() {
         "kdbx" => {
-            let db_pass: String =
-                match rpassword::prompt_password("Enter the password to your KeePass database: ") {
-                    Ok(password) => password,
-                    Err(e) => {
-                        eprintln!("Error: {:?}", e);
-                        return None;
-                    }
-                };
+            let db_pass: String = match db_pass {
+                Some(db_pass) => db_pass,
+                None => rpassword::prompt_password(format!(
+                    "Enter the password to your KeePass database ({}): ",
+                    file_path.display()
+                ))?,
+            };
 
-            build_entries_from_keepass_db(file_path, db_pass, keyfile_path)
+            read_keepass_db(file_path, db_pass, keyfile_path)
         }
-        "csv" => build_entries_from_csv(file_path),
-        _ => None,
+        "csv" => Ok(DatabaseEntries {
+            entries: build_entries_from_csv(file_path)?,
+            unreadable: vec![],
+        }),
+        _ => Err(Error::UnsupportedFileType(file_path)),
     }
 }
 
@@ -76,17 +132,14 @@ fn get_file_extension(file_path: &Path) -> Option<String> {
     //     None => None,
     // }
 }
-pub fn present_breached_entries(
-    breached_entries: &[Entry],
-    output_dest: &Destination,
-) -> std::io::Result<()> {
-    if !breached_entries.is_empty() {
+pub fn present_breached_entries(report: &Report, output_dest: &Destination) -> Result<(), Error> {
+    if report.count(Check::Breached) > 0 {
         write_to(
             output_dest,
             "The following entries have passwords contained in the list of breached passwords:",
         )?;
-        for breached_entry in breached_entries {
-            write_to(output_dest, format!("   - {}", breached_entry))?;
+        for finding in report.findings_for(Check::Breached) {
+            write_to(output_dest, format!("   - {}", finding.entry))?;
         }
     } else {
         write_to(
@@ -97,7 +150,7 @@ pub fn present_breached_entries(
     Ok(())
 }
 
-pub fn check_database_online(entries: &[Entry]) -> reqwest::Result<Vec<Entry>> {
+pub fn check_database_online(entries: &[Entry]) -> Result<Vec<Entry>, Error> {
     let mut breached_entries: Vec<Entry> = Vec::new();
     for entry in entries {
         let appearances = check_password_online(&entry.pass)?;
@@ -126,10 +179,13 @@ fn check_password_online(pass: &str) -> reqwest::Result<usize> {
     // Where N is the number of times that password has appeared.
 
     for line in body.lines() {
-        let this_suffix = &line[..35];
-        let this_number_of_matches = line[36..].parse::<usize>().unwrap_or(1); // if error parsing the number of matches, at least record one match
+        let (this_suffix, this_number_of_matches) = match line.split_once(':') {
+            Some(parts) => parts,
+            None => continue,
+        };
         if this_suffix == suffix {
-            return Ok(this_number_of_matches);
+            // if error parsing the number of matches, at least record one match
+            return Ok(this_number_of_matches.trim().parse::<usize>().unwrap_or(1));
         }
     }
     Ok(0)
@@ -140,11 +196,19 @@ pub fn check_database_offline(
     entries: &[Entry],
     progress_bar_visibility: &VisibilityPreference,
     breached_password_state: BreachedPasswordState,
-) -> io::Result<Vec<Entry>> {
+) -> Result<Vec<Entry>, Error> {
     let mut this_chunk = Vec::new();
     let mut breached_entries: Vec<Entry> = Vec::new();
 
-    let f = File::open(hash_file)?;
+    let f = match File::open(&hash_file) {
+        Ok(f) => f,
+        Err(e) => {
+            return Err(Error::Open {
+                path: hash_file,
+                source: e,
+            });
+        }
+    };
     let passwords_file_size = f.metadata()?.len() as usize;
 
     // times via `cargo test --release can_check_offline --no-run && time cargo test --release can_check_offline -- --nocapture`
@@ -166,14 +230,11 @@ pub fn check_database_offline(
         // let this_line = line?[..40].to_string();
         this_chunk.push(line?); //.to_string());
         if this_chunk.len() * 48 > chunk_size {
-            match check_this_chunk(entries, &this_chunk, &breached_password_state) {
-                Ok(mut vec_of_breached_entries) => {
-                    breached_entries.append(&mut vec_of_breached_entries)
-                }
-                Err(e) => {
-                    eprintln!("Error checking passwords against hash file: {}", e)
-                }
-            }
+            breached_entries.append(&mut check_this_chunk(
+                entries,
+                &this_chunk,
+                &breached_password_state,
+            ));
             if progress_bar_visibility == &VisibilityPreference::Show {
                 pb.inc(chunk_size as u64);
             }
@@ -181,10 +242,11 @@ pub fn check_database_offline(
         }
     }
     // Append the very last chunk for breached entries
-    match check_this_chunk(entries, &this_chunk, &breached_password_state) {
-        Ok(mut vec_of_breached_entries) => breached_entries.append(&mut vec_of_breached_entries),
-        Err(e) => eprintln!("Error checking passwords against hash file: {}", e),
-    }
+    breached_entries.append(&mut check_this_chunk(
+        entries,
+        &this_chunk,
+        &breached_password_state,
+    ));
     if progress_bar_visibility == &VisibilityPreference::Show {
         pb.finish_with_message("Done.");
     }
@@ -195,12 +257,16 @@ fn check_this_chunk(
     entries: &[Entry],
     chunk: &[String],
     breached_password_state: &BreachedPasswordState,
-) -> io::Result<Vec<Entry>> {
+) -> Vec<Entry> {
     let mut breached_entries = Vec::new();
 
     for line in chunk {
         if breached_password_state == &BreachedPasswordState::Sha1 {
-            let this_hash = &line[..40];
+            // Skip anything too short to be a SHA-1 hash, like a blank line at the end of the file
+            let this_hash = match line.get(..40) {
+                Some(hash) => hash,
+                None => continue,
+            };
 
             for entry in entries {
                 if this_hash == entry.digest {
@@ -215,10 +281,10 @@ fn check_this_chunk(
             }
         }
     }
-    Ok(breached_entries)
+    breached_entries
 }
 
-pub fn make_digest_map(entries: &[Entry]) -> io::Result<HashMap<String, Vec<Entry>>> {
+pub fn make_digest_map(entries: &[Entry]) -> Result<HashMap<String, Vec<Entry>>, Error> {
     let mut digest_map: HashMap<String, Vec<Entry>> = HashMap::new();
     for entry in entries {
         digest_map
@@ -230,26 +296,51 @@ pub fn make_digest_map(entries: &[Entry]) -> io::Result<HashMap<String, Vec<Entr
     Ok(digest_map)
 }
 
-// Clippy told me "warning: parameter of type `HashMap` should be generalized over different hashers"
-pub fn present_duplicated_entries<S: ::std::hash::BuildHasher>(
-    digest_map: HashMap<String, Vec<Entry>, S>,
-    output_dest: &Destination,
-) -> std::io::Result<()> {
-    let mut has_duplicated_entries = false;
-    for group in digest_map.values() {
-        if group.len() > 1 {
+/// Re-use across sites is listed first, then entries for the same account that share a
+/// password, which is much less of a worry
+pub fn present_duplicated_entries(report: &Report, output_dest: &Destination) -> Result<(), Error> {
+    let mut groups: BTreeMap<usize, Vec<&EntrySummary>> = BTreeMap::new();
+    let mut same_account_details: HashMap<usize, &str> = HashMap::new();
+    for finding in report.findings_for(Check::Duplicate) {
+        let group = finding.duplicate_group.unwrap_or_default();
+        groups.entry(group).or_default().push(&finding.entry);
+        if finding.same_account {
+            same_account_details.insert(group, finding.detail.as_deref().unwrap_or_default());
+        }
+    }
+    // Only bother naming each entry's database if we're auditing more than one
+    let all_entries: Vec<&EntrySummary> = groups.values().flatten().copied().collect();
+    let show_sources = is_cross_database(&all_entries);
+    for (number, group) in &groups {
+        if let Some(detail) = same_account_details.get(number) {
+            write_to(
+                output_dest,
+                format!(
+                    "The following entries are the {}, and have the same password. This is probably fine, but you may want to merge them:\n",
+                    detail
+                ),
+            )?;
+        } else if is_cross_database(group) {
+            write_to(
+                output_dest,
+                "The following entries, from different databases, have the same password:\n",
+            )?;
+        } else {
             write_to(
                 output_dest,
                 "The following entries have the same password:\n",
             )?;
-            for entry in group {
+        }
+        for entry in group {
+            if show_sources {
+                write_to(output_dest, format!("   - {} (in {})", entry, entry.source))?;
+            } else {
                 write_to(output_dest, format!("   - {}", entry))?;
             }
-            has_duplicated_entries = true;
         }
     }
 
-    if has_duplicated_entries {
+    if !groups.is_empty() {
         write_to(
             output_dest,
             "\nRe-using passwords for multiple accounts is potentially dangerous. Recommend you change passwords until you have no duplicates.\n--------------------------------\n",
@@ -259,54 +350,336 @@ pub fn present_duplicated_entries<S: ::std::hash::BuildHasher>(
     }
 }
 
-pub fn check_for_and_display_weak_passwords(
+/// Whether the given entries (e.g. a group of entries sharing a password) come from more than
+/// one database
+pub fn is_cross_database<T: EntryIdentity>(entries: &[T]) -> bool {
+    entries
+        .iter()
+        .any(|entry| entry.source() != entries[0].source())
+}
+
+/// If the given entries (e.g. a group of entries sharing a password) are all the same account,
+/// i.e. they have the same username on the same registrable domain, like `accounts.google.com`
+/// and `mail.google.com`, returns that domain. Usernames are compared case-insensitively.
+pub fn shared_account_domain(entries: &[Entry]) -> Option<String> {
+    let first = entries.first()?;
+    if first.username.is_empty() {
+        return None;
+    }
+    let domain_of = |entry: &Entry| {
+        context::host(&entry.url).and_then(|host| context::registrable_domain(&host))
+    };
+    let domain = domain_of(first)?;
+    entries
+        .iter()
+        .all(|entry| {
+            entry.username.eq_ignore_ascii_case(&first.username)
+                && domain_of(entry).as_ref() == Some(&domain)
+        })
+        .then_some(domain)
+}
+
+/// Passwords zxcvbn scores below this are weak, unless told otherwise with `--min-score`
+pub const DEFAULT_MIN_SCORE: u8 = 4;
+
+/// A password zxcvbn considers weak, along with its feedback on why
+#[derive(Debug, Clone)]
+pub struct WeakPassword {
+    pub entry: Entry,
+    /// zxcvbn's score, from 0 (weakest) to 4
+    pub score: u8,
+    /// zxcvbn's estimate of how many guesses it would take to find the password
+    pub guesses: u64,
+    pub guesses_log10: f64,
+    pub crack_times: CrackTimes,
+    pub warning: Option<String>,
+    pub suggestions: Vec<String>,
+    /// Words from `--banned-words` that the password contains
+    pub banned_words: Vec<String>,
+}
+
+/// How long zxcvbn reckons it would take to guess a password, in seconds and in words
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CrackTimes {
+    /// Guessing online against a service that allows 100 guesses an hour
+    pub online_throttled_seconds: u64,
+    pub online_throttled: String,
+    /// Guessing offline against a fast hash like SHA-1, at ten billion guesses a second
+    pub offline_fast_hash_seconds: u64,
+    pub offline_fast_hash: String,
+}
+
+impl From<zxcvbn::time_estimates::CrackTimes> for CrackTimes {
+    fn from(crack_times: zxcvbn::time_estimates::CrackTimes) -> Self {
+        let online_throttled = crack_times.online_throttling_100_per_hour();
+        let offline_fast_hash = crack_times.offline_fast_hashing_1e10_per_second();
+        CrackTimes {
+            online_throttled_seconds: std::time::Duration::from(online_throttled).as_secs(),
+            online_throttled: online_throttled.to_string(),
+            offline_fast_hash_seconds: std::time::Duration::from(offline_fast_hash).as_secs(),
+            offline_fast_hash: offline_fast_hash.to_string(),
+        }
+    }
+}
+
+/// Finds passwords that zxcvbn scores below `min_score` (0 to 4). Passwords made of the entry's
+/// details (see `context::user_inputs`) or of `context_words` score lower. Passwords that
+/// contain any of `banned_words` are weak whatever their score.
+pub fn find_weak_passwords(
     entries: &[Entry],
-    output_dest: &Destination,
-) -> std::io::Result<()> {
-    write_to(output_dest, "\n--------------------------------")?;
+    min_score: u8,
+    context_words: &[String],
+    banned_words: &[String],
+) -> Result<Vec<WeakPassword>, Error> {
+    let context_words: Vec<String> = context_words.iter().chain(banned_words).cloned().collect();
+    let mut weak_passwords: Vec<WeakPassword> = vec![];
     for entry in entries {
-        let estimate = match zxcvbn(&entry.pass, &[&entry.title, &entry.username]) {
-            Ok(estimate) => estimate,
-            Err(e) => panic!("Error getting password strength estimate: {}", e),
-        };
-        // entry.pass.len();
-        if estimate.score() < 4 {
-            write_to(output_dest, format!("Your password for {} is weak.", entry))?;
-            give_feedback(estimate.feedback(), output_dest)?;
-            write_to(output_dest, "\n--------------------------------")?;
+        let user_inputs = context::user_inputs(entry, &context_words);
+        let user_inputs: Vec<&str> = user_inputs.iter().map(String::as_str).collect();
+        let estimate = zxcvbn(&entry.pass, &user_inputs)?;
+        let banned_words = banned::banned_words_in(&entry.pass, banned_words);
+        if estimate.score() < min_score || !banned_words.is_empty() {
+            let (warning, suggestions) = match estimate.feedback() {
+                Some(feedback) => (
+                    feedback.warning().map(|warning| warning.to_string()),
+                    feedback
+                        .suggestions()
+                        .iter()
+                        .map(|suggestion| suggestion.to_string())
+                        .collect(),
+                ),
+                None => (None, vec![]),
+            };
+            weak_passwords.push(WeakPassword {
+                entry: entry.clone(),
+                score: estimate.score(),
+                guesses: estimate.guesses(),
+                guesses_log10: estimate.guesses_log10(),
+                crack_times: CrackTimes::from(estimate.crack_times()),
+                warning,
+                suggestions,
+                banned_words,
+            });
+        }
+    }
+    Ok(weak_passwords)
+}
+
+/// Prints a warning and suggestions for each weak password
+pub fn present_weak_passwords(report: &Report, output_dest: &Destination) -> Result<(), Error> {
+    write_to(output_dest, "\n--------------------------------")?;
+    for finding in report.findings_for(Check::Weak) {
+        write_to(
+            output_dest,
+            format!("Your password for {} is weak.", finding.entry),
+        )?;
+        if let (Some(score), Some(crack_times)) = (finding.score, &finding.crack_times) {
+            write_to(
+                output_dest,
+                format!(
+                    "Score: {}/4. It could be guessed in {} online (throttled), or in {} offline (fast hash).\n",
+                    score, crack_times.online_throttled, crack_times.offline_fast_hash
+                ),
+            )?;
         }
+        if let Some(detail) = &finding.detail {
+            write_to(output_dest, format!("It {}.\n", detail))?;
+        }
+        give_feedback(finding, output_dest)?;
+        write_to(output_dest, "\n--------------------------------")?;
     }
     Ok(())
 }
 
-fn give_feedback(
-    feedback: &Option<zxcvbn::feedback::Feedback>,
+/// Finds entries whose password hasn't been changed in more than `max_age_days` days, sorted
+/// oldest first. Entries without a last-modification time are skipped.
+pub fn find_stale_entries(entries: &[Entry], max_age_days: u32) -> Vec<Entry> {
+    let now = chrono::Utc::now().naive_utc();
+    let mut stale_entries: Vec<Entry> = entries
+        .iter()
+        .filter(|entry| match entry.modified {
+            Some(modified) => (now - modified).num_days() > i64::from(max_age_days),
+            None => false,
+        })
+        .cloned()
+        .collect();
+    stale_entries.sort_by_key(|entry| entry.modified);
+    stale_entries
+}
+
+/// Old passwords that are also breached or re-used are listed first, since those are the
+/// ones most worth changing.
+pub fn present_stale_entries(
+    report: &Report,
+    max_age_days: u32,
     output_dest: &Destination,
-) -> std::io::Result<()> {
-    match feedback {
-        Some(feedback) => {
-            if let Some(warning) = feedback.warning() {
-                write_to(output_dest, format!("Warning: {}\n", warning))?;
-            }
-            write_to(output_dest, "Suggestions:")?;
-            for suggestion in feedback.suggestions() {
-                write_to(output_dest, format!("   - {}", suggestion))?;
+) -> Result<(), Error> {
+    if report.count(Check::Stale) == 0 {
+        return write_to(
+            output_dest,
+            format!(
+                "\nNone of your passwords are older than {} days.",
+                max_age_days
+            ),
+        );
+    }
+    let (high_priority, other): (Vec<&Finding>, Vec<&Finding>) = report
+        .findings_for(Check::Stale)
+        .partition(|finding| finding.high_priority);
+    let now = chrono::Utc::now().naive_utc();
+    if !high_priority.is_empty() {
+        write_to(
+            output_dest,
+            format!(
+                "\nHIGH PRIORITY: The following entries have passwords older than {} days that are also breached or re-used:",
+                max_age_days
+            ),
+        )?;
+        for finding in &high_priority {
+            write_to(output_dest, format!("   - {}", describe_age(finding, now)))?;
+        }
+    }
+    if !other.is_empty() {
+        write_to(
+            output_dest,
+            format!(
+                "\nThe following entries have passwords older than {} days:",
+                max_age_days
+            ),
+        )?;
+        for finding in &other {
+            write_to(output_dest, format!("   - {}", describe_age(finding, now)))?;
+        }
+    }
+    Ok(())
+}
+
+fn describe_age(finding: &Finding, now: chrono::NaiveDateTime) -> String {
+    match finding.date {
+        Some(modified) => format!(
+            "{} (last changed {}, {} days ago)",
+            finding.entry,
+            modified.date(),
+            (now - modified).num_days()
+        ),
+        None => finding.entry.to_string(),
+    }
+}
+
+/// Splits entries that have the KeePass "expires" flag set into those that have already
+/// expired and those that will expire within `within_days` days. Both are sorted by expiry date.
+pub fn find_expiring_entries(entries: &[Entry], within_days: u32) -> (Vec<Entry>, Vec<Entry>) {
+    let now = chrono::Utc::now().naive_utc();
+    let horizon = now + chrono::Duration::days(i64::from(within_days));
+    let mut expired_entries: Vec<Entry> = vec![];
+    let mut expiring_entries: Vec<Entry> = vec![];
+    for entry in entries {
+        if let (true, Some(expiry)) = (entry.expires, entry.expiry) {
+            if expiry <= now {
+                expired_entries.push(entry.clone());
+            } else if expiry <= horizon {
+                expiring_entries.push(entry.clone());
             }
         }
-        None => write_to(output_dest, "No suggestions.")?,
+    }
+    expired_entries.sort_by_key(|entry| entry.expiry);
+    expiring_entries.sort_by_key(|entry| entry.expiry);
+    (expired_entries, expiring_entries)
+}
+
+pub fn present_expiring_entries(
+    report: &Report,
+    within_days: u32,
+    output_dest: &Destination,
+) -> Result<(), Error> {
+    if report.count(Check::Expired) == 0 {
+        return write_to(
+            output_dest,
+            format!(
+                "\nNone of your entries have expired or will expire in the next {} days.",
+                within_days
+            ),
+        );
+    }
+    let now = chrono::Utc::now().naive_utc();
+    let (expired, expiring): (Vec<&Finding>, Vec<&Finding>) = report
+        .findings_for(Check::Expired)
+        .partition(|finding| finding.date.is_some_and(|expiry| expiry <= now));
+    if !expired.is_empty() {
+        write_to(output_dest, "\nThe following entries have expired:")?;
+        for finding in expired {
+            write_to(output_dest, format!("   - {}", describe_expiry(finding)))?;
+        }
+    }
+    if !expiring.is_empty() {
+        write_to(
+            output_dest,
+            format!(
+                "\nThe following entries will expire in the next {} days:",
+                within_days
+            ),
+        )?;
+        for finding in expiring {
+            write_to(output_dest, format!("   - {}", describe_expiry(finding)))?;
+        }
+    }
+    Ok(())
+}
+
+fn describe_expiry(finding: &Finding) -> String {
+    match finding.date {
+        Some(expiry) => format!("{} (expires {})", finding.entry, expiry.date()),
+        None => finding.entry.to_string(),
+    }
+}
+
+fn give_feedback(finding: &Finding, output_dest: &Destination) -> Result<(), Error> {
+    if finding.warning.is_none() && finding.suggestions.is_empty() {
+        return write_to(output_dest, "No suggestions.");
+    }
+    if let Some(warning) = &finding.warning {
+        write_to(output_dest, format!("Warning: {}\n", warning))?;
+    }
+    write_to(output_dest, "Suggestions:")?;
+    for suggestion in &finding.suggestions {
+        write_to(output_dest, format!("   - {}", suggestion))?;
+    }
+    Ok(())
+}
+
+/// Health score and how many findings each check turned up, for the end of the report
+pub fn present_summary(report: &Report, output_dest: &Destination) -> Result<(), Error> {
+    let summary = report.summary();
+    write_to(output_dest, "\n================================\nSummary")?;
+    write_to(
+        output_dest,
+        format!("Health score: {}/100", summary.health_score),
+    )?;
+    write_to(
+        output_dest,
+        format!(
+            "{} of {} entries have problems.",
+            summary.entries_with_findings, summary.entries_checked
+        ),
+    )?;
+    for (check, count) in summary.counts {
+        write_to(
+            output_dest,
+            format!("   - {}: {}", report::section_title(check), count),
+        )?;
     }
     Ok(())
 }
 
-pub fn gets() -> io::Result<String> {
+pub fn gets() -> Result<String, Error> {
     let mut input = String::new();
     match io::stdin().read_line(&mut input) {
         Ok(_n) => Ok(input.trim_end_matches('\n').to_string()),
-        Err(error) => Err(error),
+        Err(error) => Err(Error::Io(error)),
     }
 }
 
-pub fn create_file(dest: &Destination) -> std::io::Result<()> {
+pub fn create_file(dest: &Destination) -> Result<(), Error> {
     match dest {
         Destination::FilePath(file_path) => {
             match File::open(file_path) {
@@ -318,7 +691,7 @@ pub fn create_file(dest: &Destination) -> std::io::Result<()> {
                     if gets()? == "y" {
                         File::create(file_path)?;
                     } else {
-                        panic!("OK, exiting");
+                        return Err(Error::Aborted);
                     }
                 }
                 Err(_e) => {
@@ -333,17 +706,17 @@ pub fn create_file(dest: &Destination) -> std::io::Result<()> {
 pub fn write_to<StringLike: Into<String>>(
     dest: &Destination,
     output: StringLike,
-) -> std::io::Result<()> {
+) -> Result<(), Error> {
     match dest {
         Destination::FilePath(file_path) => {
             let mut f = OpenOptions::new().append(true).open(file_path)?;
-            writeln!(f, "{}", &output.into())
+            Ok(writeln!(f, "{}", &output.into())?)
         }
         Destination::Terminal => {
             // println!("{}", &output);
             let stdout = io::stdout(); // get the global stdout entity
             let mut handle = stdout.lock(); // acquire a lock on it
-            writeln!(handle, "{}", output.into())
+            Ok(writeln!(handle, "{}", output.into())?)
         }
     }
 }
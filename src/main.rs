@@ -1,20 +1,56 @@
+use clap::CommandFactory;
 use clap::Parser;
+use clap::ValueEnum;
+use clap::error::ErrorKind;
+use medic::allowlist::*;
+use medic::banned::read_banned_words;
+use medic::context::*;
+use medic::diff::*;
 use medic::entries::Entry;
+use medic::history::*;
+use medic::patterns::*;
+use medic::policy::*;
+use medic::report::Format;
+use medic::secrets::*;
+use medic::similar::*;
+use medic::tags::*;
 use medic::*;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+// Exit codes. 2 is also what clap uses for usage errors.
+const EXIT_FINDINGS: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_UNLOCK_FAILED: u8 = 3;
+const EXIT_INPUT_OR_NETWORK_ERROR: u8 = 4;
 
 /// Check the "health" of passwords in a KeePass database
 #[derive(Parser, Debug)]
-#[clap(name = "medic", version)]
+#[clap(
+    name = "medic",
+    version,
+    after_help = "Exit codes: 0 if no problems were found, 1 if problems were found (see --fail-on), 2 on usage errors, 3 if a database couldn't be unlocked, 4 if a file couldn't be read or written or HaveIBeenPwned couldn't be reached."
+)]
 struct Args {
     /// Use debug mode, which, among other things, displays received arguments and hides
     /// progress bar when checking passwords against a file of hashed passwords
     #[clap(long = "debug")]
     debug: bool,
 
-    /// Provide key file, if unlocking the KeePass databases requires one
-    #[clap(short = 'k', long = "keyfile")]
-    keyfile: Option<PathBuf>,
+    /// Provide key file, if unlocking the KeePass database requires one. When checking several
+    /// databases, say which database each key file is for, like "alice.kdbx=alice.key". Can be
+    /// given more than once.
+    #[clap(short = 'k', long = "keyfile", value_name = "[DATABASE=]KEYFILE", value_parser = parse_database_file)]
+    keyfiles: Vec<(Option<PathBuf>, PathBuf)>,
+
+    /// Read a KeePass database's password from the first line of a file, rather than asking for
+    /// it. When checking several databases, say which database each file is for, like
+    /// "alice.kdbx=alice-password.txt". Can be given more than once.
+    #[clap(long = "password-file", value_name = "[DATABASE=]FILE", value_parser = parse_database_file)]
+    password_files: Vec<(Option<PathBuf>, PathBuf)>,
 
     /// Check passwords against breached passwords online via the HaveIBeenPwned API. More info
     /// here:
@@ -34,120 +70,624 @@ struct Args {
     #[clap(short = 'd', long = "duplicate")]
     check_duplicate: bool,
 
+    /// Check database for passwords that are similar to each other, like "Summer2023!" and
+    /// "Summer2024!", or the same password with a different site's name in it
+    #[clap(long = "similar")]
+    check_similar: bool,
+
+    /// Check database for families of passwords made from the same template, like
+    /// "Base!GitHub#2024" and "Base!Twitter#2024": at least three different passwords that
+    /// start or end with the same characters
+    #[clap(long = "patterns")]
+    check_patterns: bool,
+
+    /// How many characters passwords have to share at their start or end to count as a
+    /// pattern family
+    #[clap(
+        long = "pattern-length",
+        value_name = "CHARS",
+        default_value_t = DEFAULT_PATTERN_LENGTH,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pattern_length: u32,
+
     /// Check database for weak passwords
     #[clap(short = 'w', long = "weak")]
     check_weak: bool,
 
+    /// Passwords zxcvbn scores below this, from 0 to 4, are weak
+    #[clap(
+        long = "min-score",
+        value_name = "SCORE",
+        default_value_t = DEFAULT_MIN_SCORE,
+        value_parser = clap::value_parser!(u8).range(0..=4)
+    )]
+    min_score: u8,
+
+    /// Provide a TOML file with your organization's password policy, and report passwords that
+    /// break it. See the readme for the format.
+    #[clap(long = "policy", value_name = "FILE")]
+    policy: Option<PathBuf>,
+
+    /// Check database for passwords that contain the entry's own username, title, website or
+    /// email address, even spelled in leetspeak or a different case
+    #[clap(long = "context")]
+    check_context: bool,
+
+    /// Provide file of words, one per line, that passwords shouldn't be built from, like your
+    /// company's name. Entries' titles, usernames, domains, groups and email addresses are
+    /// always taken into account. Requires -w.
+    #[clap(long = "context-words", value_name = "FILE", requires = "check_weak")]
+    context_words: Option<PathBuf>,
+
+    /// Provide file of words, one per line, that must never appear in passwords, like company
+    /// names or product codenames. Passwords containing one are weak whatever their score, even
+    /// if it's spelled in leetspeak or a different case. Requires -w.
+    #[clap(long = "banned-words", value_name = "FILE", requires = "check_weak")]
+    banned_words: Option<PathBuf>,
+
+    /// Check database for passwords that haven't been changed in more than this many days
+    #[clap(long = "max-age", value_name = "DAYS")]
+    max_age: Option<u32>,
+
+    /// Check database for entries that have expired or will expire within this many days
+    #[clap(long = "expiring", value_name = "DAYS")]
+    expiring: Option<u32>,
+
+    /// Also audit each entry's password history: flag current passwords that were used before,
+    /// and check previous passwords against breached passwords
+    #[clap(long = "history")]
+    check_history: bool,
+
+    /// Also run custom string fields whose names match this pattern (e.g. "*token*") through the
+    /// breach, duplicate and weak checks. Can be given more than once.
+    #[clap(long = "field", value_name = "PATTERN")]
+    fields: Vec<String>,
+
+    /// Warn about entries whose notes contain something that looks like a password
+    #[clap(long = "scan-notes")]
+    scan_notes: bool,
+
+    /// Only check entries with this tag. Can be given more than once.
+    #[clap(long = "tag", value_name = "TAG")]
+    tags: Vec<String>,
+
+    /// Weight findings for entries with this tag, e.g. "prod=3". Entries weigh 1 by default,
+    /// and heavier entries are listed first. Can be given more than once.
+    #[clap(long = "tag-weight", value_name = "TAG=WEIGHT", value_parser = parse_tag_weight)]
+    tag_weights: Vec<(String, u32)>,
+
+    /// Provide a TOML file of findings you've accepted, like one SSO password stored under
+    /// several entries, each with a reason. Allowed findings are listed separately and don't
+    /// count towards --fail-on. See the readme for the format.
+    #[clap(long = "allowlist", value_name = "FILE")]
+    allowlist: Option<PathBuf>,
+
+    /// Which kinds of findings make medic exit with status 1. Defaults to all of them.
+    #[clap(
+        long = "fail-on",
+        value_name = "CHECKS",
+        value_enum,
+        value_delimiter = ','
+    )]
+    fail_on: Vec<Check>,
+
+    /// How to print results. "json" and "csv" print machine-readable reports, "markdown" a table
+    /// per check, and "html" a self-contained dashboard. None of these include passwords.
+    #[clap(long = "format", value_enum, default_value = "text")]
+    format: Format,
+
     /// Print results of health check to a file
     #[clap(short = 'o', long = "output")]
     output: Option<String>,
 
-    /// KeePass database to check. Can either be a kdbx file or an exported CSV version of a
-    /// KeePass database.
-    #[clap(name = "KEEPASS DATABASE FILE")]
-    keepass_db: PathBuf,
+    /// Compare two reports and list the findings that were resolved, are new, or are unchanged.
+    /// Give exactly two files, old then new. Each can be a report saved with --format json, or a
+    /// database to check now.
+    #[clap(long = "diff")]
+    diff: bool,
+
+    /// KeePass database(s) to check. Each can either be a kdbx file or an exported CSV version of
+    /// a KeePass database. Passwords re-used across databases are reported as duplicates.
+    #[clap(name = "KEEPASS DATABASE FILE", required = true)]
+    keepass_dbs: Vec<PathBuf>,
 }
 
-fn main() {
+fn main() -> ExitCode {
     let opt = Args::parse();
     if opt.debug {
         println!("Arguments received:\n{:?}", opt);
     }
-    let keepass_db_file_path = opt.keepass_db;
-    let passwords_file: Option<PathBuf> = opt.passwords_file;
-    let hash_file: Option<PathBuf> = opt.hash_file;
-    let keyfile: Option<PathBuf> = opt.keyfile;
-    let check_online = opt.online;
-    let progress_bar_visibility = if opt.debug {
-        VisibilityPreference::Hide
+    // Diffing two saved reports doesn't need any checks
+    let only_reports = opt.diff && opt.keepass_dbs.iter().all(|path| is_json_report(path));
+    if !only_reports
+        && opt.passwords_file.is_none()
+        && opt.hash_file.is_none()
+        && !opt.online
+        && !opt.check_duplicate
+        && !opt.check_similar
+        && !opt.check_context
+        && !opt.check_patterns
+        && !opt.check_weak
+        && opt.max_age.is_none()
+        && opt.expiring.is_none()
+        && !opt.check_history
+        && !opt.scan_notes
+        && opt.policy.is_none()
+    {
+        eprintln!("Whoops! I have nothing the check against.");
+        eprintln!(
+            "You must either:\n1. Provide a file with hashes of passwords to check against \nOR\n2. Use the --online flag to check your passwords online via HaveIBeenPwned API\nOR\n3. Use one or both of -d or -w flags to check for duplicate and/or weak passwords\nOR\n4. Use --max-age and/or --expiring to check for old or expiring passwords\nOR\n5. Use --history to check for re-used previous passwords\nOR\n6. Use --scan-notes to look for passwords stored in notes\nOR\n7. Use --policy to check passwords against your password policy\nOR\n8. Use --similar to check for passwords that are similar to each other\nOR\n9. Use --context to check for passwords built from their entry's username, title or website\nOR\n10. Use --patterns to check for families of passwords made from the same template"
+        );
+        eprintln!("Run --help for more information");
+        return ExitCode::from(EXIT_USAGE);
+    }
+    for (files, flag, what) in [
+        (&opt.keyfiles, "--keyfile", "key file"),
+        (&opt.password_files, "--password-file", "password file"),
+    ] {
+        if let Err(message) = check_database_files(&opt, files, flag, what) {
+            Args::command()
+                .error(ErrorKind::ArgumentConflict, message)
+                .exit();
+        }
+    }
+    if opt.diff && opt.keepass_dbs.len() != 2 {
+        Args::command()
+            .error(
+                ErrorKind::WrongNumberOfValues,
+                "--diff takes exactly two reports or databases: the old one, then the new one",
+            )
+            .exit();
+    }
+    if opt.diff && opt.format == Format::Html {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--diff can't print HTML yet. Try --format text, json, csv or markdown.",
+            )
+            .exit();
+    }
+
+    let fail_on = if opt.fail_on.is_empty() {
+        Check::value_variants().to_vec()
     } else {
-        VisibilityPreference::Show
+        opt.fail_on.clone()
     };
-    let output_dest: Destination = match opt.output {
-        Some(file_path) => Destination::FilePath(file_path),
+    match run(opt) {
+        Ok(findings) => {
+            if findings.iter().any(|check| fail_on.contains(check)) {
+                ExitCode::from(EXIT_FINDINGS)
+            } else {
+                ExitCode::SUCCESS
+            }
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::from(exit_code_for(&e))
+        }
+    }
+}
+
+fn exit_code_for(e: &Error) -> u8 {
+    match e {
+        Error::Unlock(_) => EXIT_UNLOCK_FAILED,
+        Error::Open { .. }
+        | Error::UnsupportedFileType(_)
+        | Error::Parse(_)
+        | Error::Network(_)
+        | Error::Estimate(_)
+        | Error::Io(_)
+        | Error::Aborted => EXIT_INPUT_OR_NETWORK_ERROR,
+    }
+}
+
+/// Runs the requested checks and prints the report, or compares two reports. Returns the kinds
+/// of checks that found (new) problems.
+fn run(opt: Args) -> Result<HashSet<Check>, Error> {
+    let output_dest: Destination = match &opt.output {
+        Some(file_path) => Destination::FilePath(file_path.to_string()),
         None => Destination::Terminal,
     };
-    match &output_dest {
-        Destination::FilePath(file_path) => {
-            create_file(&Destination::FilePath(file_path.to_string()))
-                .expect("Couldn't write to file");
+    create_file(&output_dest)?;
+
+    if opt.diff {
+        let old_report = read_or_check(&opt, 0)?;
+        let new_report = read_or_check(&opt, 1)?;
+        let diff = ReportDiff::new(&old_report, &new_report);
+        match opt.format {
+            Format::Text => present_report_diff(&diff, &output_dest)?,
+            Format::Json => write_to(&output_dest, diff.to_json()?)?,
+            Format::Csv => write_to(&output_dest, diff.to_csv()?)?,
+            Format::Markdown => write_to(&output_dest, diff.to_markdown())?,
+            Format::Html => unreachable!("HTML diffs are rejected when parsing arguments"),
         }
-        Destination::Terminal => (),
+        return Ok(diff.new.iter().map(|finding| finding.check).collect());
     }
 
-    if hash_file.is_none() && !check_online && !opt.check_duplicate && !opt.check_weak {
-        eprintln!("Whoops! I have nothing the check against.");
-        eprintln!(
-            "You must either:\n1. Provide a file with hashes of passwords to check against \nOR\n2. Use the --online flag to check your passwords online via HaveIBeenPwned API\nOR\n3. Use one or both of -d or -w flags to check for duplicate and/or weak passwords"
-        );
-        eprintln!("Run --help for more information");
-        return;
+    let report = check_databases(&opt, &opt.keepass_dbs)?;
+    match opt.format {
+        Format::Text => present_report(&report, opt.max_age, opt.expiring, &output_dest)?,
+        Format::Json => write_to(&output_dest, report.to_json()?)?,
+        Format::Csv => write_to(&output_dest, report.to_csv()?)?,
+        Format::Markdown => write_to(&output_dest, report.to_markdown())?,
+        Format::Html => write_to(&output_dest, report.to_html())?,
+    }
+    Ok(report
+        .findings
+        .iter()
+        .map(|finding| finding.check)
+        .collect())
+}
+
+/// One side of a diff: either a saved JSON report, or a database to check now
+fn read_or_check(opt: &Args, i: usize) -> Result<Report, Error> {
+    let path = &opt.keepass_dbs[i];
+    if is_json_report(path) {
+        read_report(path.to_path_buf())
+    } else {
+        check_databases(opt, std::slice::from_ref(path))
     }
+}
 
-    let entries: Vec<Entry> = match get_entries(keepass_db_file_path, keyfile) {
-        Some(entries) => entries,
-        None => panic!("Didn't find any entries in provided KeePass database"),
+/// Parses a `--keyfile` or `--password-file` value: either just a file, or `DATABASE=FILE`. A
+/// file that exists is taken as is, even if its name has a `=` in it.
+fn parse_database_file(value: &str) -> Result<(Option<PathBuf>, PathBuf), String> {
+    if Path::new(value).exists() {
+        return Ok((None, PathBuf::from(value)));
+    }
+    match value.split_once('=') {
+        Some((database, file)) if !database.is_empty() && !file.is_empty() => {
+            Ok((Some(PathBuf::from(database)), PathBuf::from(file)))
+        }
+        Some(_) => Err(format!("expected DATABASE=FILE, got \"{}\"", value)),
+        None => Ok((None, PathBuf::from(value))),
+    }
+}
+
+/// A key or password file without a database is only unambiguous when there's one database to
+/// open. Files for a database have to name one of the databases given, once. `flag` and `what`
+/// say which kind of file `files` are, for error messages.
+fn check_database_files(
+    opt: &Args,
+    files: &[(Option<PathBuf>, PathBuf)],
+    flag: &str,
+    what: &str,
+) -> Result<(), String> {
+    let databases: Vec<&PathBuf> = opt
+        .keepass_dbs
+        .iter()
+        .filter(|path| !is_json_report(path))
+        .collect();
+    for (i, (database, file)) in files.iter().enumerate() {
+        match database {
+            None if databases.len() != 1 || files.len() > 1 => {
+                return Err(format!(
+                    "Say which database {} is for, like {} DATABASE={}",
+                    file.display(),
+                    flag,
+                    file.display()
+                ));
+            }
+            None => {}
+            Some(database) if !databases.contains(&database) => {
+                return Err(format!(
+                    "The {} {} is for {}, which isn't one of the databases to check",
+                    what,
+                    file.display(),
+                    database.display()
+                ));
+            }
+            Some(database)
+                if files[..i]
+                    .iter()
+                    .any(|(other, _)| other.as_ref() == Some(database)) =>
+            {
+                return Err(format!(
+                    "You gave more than one {} for {}",
+                    what,
+                    database.display()
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// The file from `files` (key files or password files) that goes with `database`, if any
+fn file_for(files: &[(Option<PathBuf>, PathBuf)], database: &Path) -> Option<PathBuf> {
+    files
+        .iter()
+        .find(|(file_database, _)| match file_database {
+            Some(file_database) => file_database == database,
+            None => true,
+        })
+        .map(|(_, file)| file.to_path_buf())
+}
+
+/// Reads a database password from the first line of a file, without its line ending
+fn read_password_file(file_path: PathBuf) -> Result<String, Error> {
+    match fs::read_to_string(&file_path) {
+        Ok(contents) => Ok(contents.lines().next().unwrap_or_default().to_string()),
+        Err(e) => Err(Error::Open {
+            path: file_path,
+            source: e,
+        }),
+    }
+}
+
+fn is_json_report(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+}
+
+/// Reads the given databases and runs the requested checks on them
+fn check_databases(opt: &Args, keepass_db_file_paths: &[PathBuf]) -> Result<Report, Error> {
+    let passwords_file: Option<PathBuf> = opt.passwords_file.clone();
+    let hash_file: Option<PathBuf> = opt.hash_file.clone();
+    let check_online = opt.online;
+    // Read before asking for any database passwords, so that a typo doesn't waste them
+    let allowlist = match &opt.allowlist {
+        Some(file_path) => Some(read_allowlist(file_path.to_path_buf())?),
+        None => None,
+    };
+    let policy = match &opt.policy {
+        Some(file_path) => Some(read_policy(file_path.to_path_buf())?),
+        None => None,
     };
+    let context_words = match &opt.context_words {
+        Some(file_path) => read_context_words(file_path.to_path_buf())?,
+        None => vec![],
+    };
+    let banned_words = match &opt.banned_words {
+        Some(file_path) => read_banned_words(file_path.to_path_buf())?,
+        None => vec![],
+    };
+    let progress_bar_visibility = if opt.debug {
+        VisibilityPreference::Hide
+    } else {
+        VisibilityPreference::Show
+    };
+
+    let db_passes: Vec<Option<String>> = keepass_db_file_paths
+        .iter()
+        .map(|path| file_for(&opt.password_files, path).map(read_password_file))
+        .map(Option::transpose)
+        .collect::<Result<_, _>>()?;
+
+    let mut entries: Vec<Entry> = vec![];
+    for (keepass_db_file_path, db_pass) in keepass_db_file_paths.iter().zip(db_passes) {
+        let is_keepass_db = keepass_db_file_path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("kdbx"));
+        if is_keepass_db {
+            eprintln!("Attempting to unlock your KeePass database...");
+        }
+        let mut database_entries = get_entries_with_password(
+            keepass_db_file_path.to_path_buf(),
+            file_for(&opt.keyfiles, keepass_db_file_path),
+            db_pass,
+        )?;
+        for entry in &database_entries.unreadable {
+            eprintln!(
+                "Error reading a password for entry titled: \"{}\", username: \"{}\", on site {}.",
+                entry.title, entry.username, entry.url
+            );
+        }
+        if is_keepass_db {
+            eprintln!("Successfully read KeePass database!");
+        }
+        entries.append(&mut database_entries.entries);
+    }
+    let tag_weights: HashMap<String, u32> = opt.tag_weights.iter().cloned().collect();
+    let mut entries = filter_by_tags(entries, &opt.tags);
+    sort_by_tag_weight(&mut entries, &tag_weights);
+    // Custom fields the user asked us to treat as secrets get the same breach, duplicate and
+    // weak checks as passwords
+    let checked_entries: Vec<Entry> =
+        [entries.clone(), custom_field_secrets(&entries, &opt.fields)].concat();
+    // Custom fields count towards their entry, rather than as entries of their own
+    let mut report = Report::new(entries.len());
+    if !tag_weights.is_empty() {
+        report.set_tag_weights(&entries, &tag_weights);
+    }
+    // Breached and re-used entries, used to prioritize other findings
+    let mut compromised_entries: Vec<Entry> = vec![];
+    let previous_passwords: Vec<Entry> = if opt.check_history {
+        previous_passwords(&entries)
+    } else {
+        vec![]
+    };
+    if opt.check_history {
+        report.add_reused_previous_passwords(&find_reused_previous_passwords(&entries));
+    }
+    if opt.scan_notes {
+        report.add_secrets_in_notes(&find_secrets_in_notes(&entries));
+    }
     if opt.check_weak {
-        match check_for_and_display_weak_passwords(&entries, &output_dest) {
-            Ok(()) => (),
-            Err(e) => panic!("Error checking for weak passwords!: {}", e),
+        report.add_weak(&find_weak_passwords(
+            &checked_entries,
+            opt.min_score,
+            &context_words,
+            &banned_words,
+        )?);
+    }
+    if opt.check_context {
+        report.add_entry_details_in_passwords(&find_entry_details_in_passwords(&checked_entries));
+    }
+    if let Some(policy) = &policy {
+        report.add_policy_violations(&find_policy_violations(&checked_entries, policy));
+    }
+    // Re-used passwords make old ones a higher priority even without -d
+    let digest_map = make_digest_map(&checked_entries)?;
+    for group in digest_map.values() {
+        if group.len() > 1 {
+            compromised_entries.extend(group.iter().cloned());
         }
     }
     if opt.check_duplicate {
-        let digest_map = match make_digest_map(&entries) {
-            Ok(map) => map,
-            Err(e) => panic!("Failed to check for duplicate passwords: {}", e),
-        };
-        present_duplicated_entries(digest_map, &output_dest)
-            .expect("Error presenting duplicate passwords");
+        report.add_duplicates(&digest_map);
+    }
+    if opt.check_similar {
+        report.add_similar(&find_similar_passwords(&checked_entries));
+    }
+    if opt.check_patterns {
+        report.add_pattern_families(&find_pattern_families(
+            &checked_entries,
+            opt.pattern_length as usize,
+        ));
     }
+    // An entry's password can be on more than one list, so the lists' digests are merged and
+    // each breached entry is only reported once. `None` if no list was checked.
+    let mut breached_digests: Option<HashSet<String>> = None;
+    let mut breached_previous_digests: Option<HashSet<String>> = None;
     if let Some(passwords_file) = passwords_file {
-        println!("Checking KeePass database against provided file of passwords");
-        let breached_entries = match check_database_offline(
-            passwords_file,
-            &entries,
-            &progress_bar_visibility,
-            BreachedPasswordState::Clear,
-        ) {
-            Ok(breached_entries) => breached_entries,
-            Err(e) => panic!("Error checking database offline: {}", e),
-        };
-
-        present_breached_entries(&breached_entries, &output_dest)
-            .expect("Error presenting breached entries");
+        eprintln!("Checking KeePass database against provided file of passwords");
+        breached_digests.get_or_insert_default().extend(
+            check_database_offline(
+                passwords_file.clone(),
+                &checked_entries,
+                &progress_bar_visibility,
+                BreachedPasswordState::Clear,
+            )?
+            .into_iter()
+            .map(|entry| entry.digest),
+        );
+
+        if opt.check_history {
+            breached_previous_digests.get_or_insert_default().extend(
+                check_database_offline(
+                    passwords_file,
+                    &previous_passwords,
+                    &progress_bar_visibility,
+                    BreachedPasswordState::Clear,
+                )?
+                .into_iter()
+                .map(|entry| entry.digest),
+            );
+        }
     }
     if let Some(hash_file) = hash_file {
-        println!("Checking KeePass database against provided file of hashed passwords");
-        let breached_entries = match check_database_offline(
-            hash_file,
-            &entries,
-            &progress_bar_visibility,
-            BreachedPasswordState::Sha1,
-        ) {
-            Ok(breached_entries) => breached_entries,
-            Err(e) => panic!("Error checking database offline: {}", e),
-        };
-
-        present_breached_entries(&breached_entries, &output_dest)
-            .expect("Error presenting breached entries");
+        eprintln!("Checking KeePass database against provided file of hashed passwords");
+        breached_digests.get_or_insert_default().extend(
+            check_database_offline(
+                hash_file.clone(),
+                &checked_entries,
+                &progress_bar_visibility,
+                BreachedPasswordState::Sha1,
+            )?
+            .into_iter()
+            .map(|entry| entry.digest),
+        );
+
+        if opt.check_history {
+            breached_previous_digests.get_or_insert_default().extend(
+                check_database_offline(
+                    hash_file,
+                    &previous_passwords,
+                    &progress_bar_visibility,
+                    BreachedPasswordState::Sha1,
+                )?
+                .into_iter()
+                .map(|entry| entry.digest),
+            );
+        }
     }
     if check_online {
-        println!(
+        eprintln!(
             "\nAre you sure you want to check the KeePass database against HaveIBeenPwned API? (y/N)"
         );
-        match gets() {
-            Ok(answer) => {
-                if answer == "y" {
-                    let breached_entries = match check_database_online(&entries) {
-                        Ok(breached_entries) => breached_entries,
-                        Err(e) => panic!("Error: {}", e),
-                    };
-                    present_breached_entries(&breached_entries, &output_dest)
-                        .expect("Error presenting breached entries");
-                }
+        if gets()? == "y" {
+            breached_digests.get_or_insert_default().extend(
+                check_database_online(&checked_entries)?
+                    .into_iter()
+                    .map(|entry| entry.digest),
+            );
+
+            if opt.check_history {
+                breached_previous_digests.get_or_insert_default().extend(
+                    check_database_online(&previous_passwords)?
+                        .into_iter()
+                        .map(|entry| entry.digest),
+                );
             }
-            Err(e) => eprintln!("Error reading your answer: {}", e),
         }
     }
+    if let Some(breached_digests) = breached_digests {
+        let mut breached_entries: Vec<Entry> = checked_entries
+            .iter()
+            .filter(|entry| breached_digests.contains(&entry.digest))
+            .cloned()
+            .collect();
+        sort_by_tag_weight(&mut breached_entries, &tag_weights);
+        report.add_breached(&breached_entries);
+        compromised_entries.extend(breached_entries);
+    }
+    if let Some(breached_previous_digests) = breached_previous_digests {
+        let breached_previous_passwords: Vec<Entry> = previous_passwords
+            .iter()
+            .filter(|entry| breached_previous_digests.contains(&entry.digest))
+            .cloned()
+            .collect();
+        report.add_breached_previous_passwords(&breached_previous_passwords);
+    }
+    if let Some(max_age) = opt.max_age {
+        report.add_stale(&find_stale_entries(&entries, max_age), &compromised_entries);
+    }
+    if let Some(within_days) = opt.expiring {
+        let (expired_entries, expiring_entries) = find_expiring_entries(&entries, within_days);
+        report.add_expiring(&expired_entries, &expiring_entries);
+    }
+    if let Some(allowlist) = allowlist {
+        report.apply_allowlist(&allowlist);
+    }
+    Ok(report)
+}
+
+/// Prints each check's section of the report in plain text
+fn present_report(
+    report: &Report,
+    max_age: Option<u32>,
+    within_days: Option<u32>,
+    output_dest: &Destination,
+) -> Result<(), Error> {
+    if report.has_run(Check::History) {
+        present_reused_previous_passwords(report, output_dest)?;
+    }
+    if report.has_run(Check::Notes) {
+        present_secrets_in_notes(report, output_dest)?;
+    }
+    if report.has_run(Check::Weak) {
+        present_weak_passwords(report, output_dest)?;
+    }
+    if report.has_run(Check::Context) {
+        present_entry_details_in_passwords(report, output_dest)?;
+    }
+    if report.has_run(Check::Policy) {
+        present_policy_violations(report, output_dest)?;
+    }
+    if report.has_run(Check::Duplicate) {
+        present_duplicated_entries(report, output_dest)?;
+    }
+    if report.has_run(Check::Similar) {
+        present_similar_entries(report, output_dest)?;
+    }
+    if report.has_run(Check::Pattern) {
+        present_pattern_families(report, output_dest)?;
+    }
+    if report.has_run(Check::Breached) {
+        present_breached_entries(report, output_dest)?;
+        if report.has_run(Check::History) {
+            present_breached_previous_passwords(report, output_dest)?;
+        }
+    }
+    if let Some(max_age) = max_age {
+        present_stale_entries(report, max_age, output_dest)?;
+    }
+    if let Some(within_days) = within_days {
+        present_expiring_entries(report, within_days, output_dest)?;
+    }
+    present_suppressed_findings(report, output_dest)?;
+    present_summary(report, output_dest)
 }
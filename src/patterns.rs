@@ -0,0 +1,146 @@
+//! Passwords made by hand from a personal template, like `Base!GitHub#2024` and
+//! `Base!Twitter#2024`. They may each look strong, but one leak gives away the template, and
+//! with it every other password in the family.
+use crate::entries::Entry;
+use crate::error::Error;
+use crate::report::{EntrySummary, Report};
+use crate::{Check, Destination, write_to};
+use std::collections::BTreeMap;
+
+/// Passwords sharing a start or end shorter than this aren't reported, unless told otherwise
+/// with `--pattern-length`
+pub const DEFAULT_PATTERN_LENGTH: u32 = 5;
+
+/// How many different passwords have to share a start or end before it counts as a pattern
+pub const MIN_FAMILY_SIZE: usize = 3;
+
+/// Entries whose passwords share a template. The shared characters themselves are left out,
+/// since they're part of each password.
+#[derive(Debug, Clone)]
+pub struct PatternFamily {
+    pub entries: Vec<Entry>,
+    /// How many characters every password in the family starts with, if they share a start
+    pub prefix_length: Option<usize>,
+    /// How many characters every password in the family ends with, if they share an end
+    pub suffix_length: Option<usize>,
+}
+
+impl PatternFamily {
+    /// The family's pattern, in words, e.g. "start with the same 5 characters"
+    pub fn describe(&self) -> String {
+        match (self.prefix_length, self.suffix_length) {
+            (Some(prefix), Some(suffix)) => format!(
+                "start with the same {} characters and end with the same {}",
+                prefix, suffix
+            ),
+            (Some(prefix), None) => format!("start with the same {} characters", prefix),
+            (None, Some(suffix)) => format!("end with the same {} characters", suffix),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// How many characters all of the given strings have in common at the start
+fn common_prefix_length(passwords: &[&[char]]) -> usize {
+    let shortest = passwords.iter().map(|p| p.len()).min().unwrap_or(0);
+    (0..shortest)
+        .take_while(|&i| passwords.iter().all(|p| p[i] == passwords[0][i]))
+        .count()
+}
+
+/// Groups different passwords by their first `length` characters, keeping the groups of at
+/// least `MIN_FAMILY_SIZE`, with the length of the whole part they have in common
+fn shared_starts(passwords: &[Vec<char>], length: usize) -> Vec<(Vec<usize>, usize)> {
+    let mut by_start: BTreeMap<&[char], Vec<usize>> = BTreeMap::new();
+    for (i, password) in passwords.iter().enumerate() {
+        if password.len() >= length {
+            by_start.entry(&password[..length]).or_default().push(i);
+        }
+    }
+    by_start
+        .into_values()
+        .filter(|members| members.len() >= MIN_FAMILY_SIZE)
+        .map(|members| {
+            let member_passwords: Vec<&[char]> =
+                members.iter().map(|&i| passwords[i].as_slice()).collect();
+            let shared_length = common_prefix_length(&member_passwords);
+            (members, shared_length)
+        })
+        .collect()
+}
+
+/// Finds families of at least `MIN_FAMILY_SIZE` different passwords that all start, or all
+/// end, with the same `min_length` or more characters. Entries with identical passwords are
+/// all included in their password's family. A family whose passwords share both a start and
+/// an end is reported once.
+pub fn find_pattern_families(entries: &[Entry], min_length: usize) -> Vec<PatternFamily> {
+    // Each different password once, with the entries that have it
+    let mut by_digest: BTreeMap<&str, Vec<&Entry>> = BTreeMap::new();
+    for entry in entries.iter().filter(|entry| !entry.pass.is_empty()) {
+        by_digest.entry(&entry.digest).or_default().push(entry);
+    }
+    let owners: Vec<&Vec<&Entry>> = by_digest.values().collect();
+    let passwords: Vec<Vec<char>> = owners
+        .iter()
+        .map(|owners| owners[0].pass.chars().collect())
+        .collect();
+    let reversed: Vec<Vec<char>> = passwords
+        .iter()
+        .map(|password| password.iter().rev().copied().collect())
+        .collect();
+
+    // Keyed by the passwords in the family, so a shared start and end are reported together
+    let mut families: BTreeMap<Vec<usize>, (Option<usize>, Option<usize>)> = BTreeMap::new();
+    let min_length = min_length.max(1);
+    for (members, length) in shared_starts(&passwords, min_length) {
+        families.entry(members).or_default().0 = Some(length);
+    }
+    for (members, length) in shared_starts(&reversed, min_length) {
+        families.entry(members).or_default().1 = Some(length);
+    }
+
+    families
+        .into_iter()
+        .map(|(members, (prefix_length, suffix_length))| PatternFamily {
+            entries: members
+                .iter()
+                .flat_map(|&i| owners[i].iter().map(|entry| (*entry).clone()))
+                .collect(),
+            prefix_length,
+            suffix_length,
+        })
+        .collect()
+}
+
+pub fn present_pattern_families(report: &Report, output_dest: &Destination) -> Result<(), Error> {
+    let mut families: BTreeMap<usize, (Option<&str>, Vec<&EntrySummary>)> = BTreeMap::new();
+    for finding in report.findings_for(Check::Pattern) {
+        let family = families
+            .entry(finding.duplicate_group.unwrap_or_default())
+            .or_default();
+        family.0 = finding.detail.as_deref();
+        family.1.push(&finding.entry);
+    }
+    if families.is_empty() {
+        return write_to(
+            output_dest,
+            "\nI didn't find any patterns shared by several of your passwords.",
+        );
+    }
+    for (pattern, entries) in families.values() {
+        write_to(
+            output_dest,
+            format!(
+                "\nThe passwords of the following entries all {}:",
+                pattern.unwrap_or("follow the same pattern")
+            ),
+        )?;
+        for entry in entries {
+            write_to(output_dest, format!("   - {}", entry))?;
+        }
+    }
+    write_to(
+        output_dest,
+        "\nPasswords made from the same template give each other away: if one leaks, the rest are easy to guess. Recommend you use a password generator instead.\n--------------------------------\n",
+    )
+}
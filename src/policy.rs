@@ -0,0 +1,219 @@
+//! Checking passwords against an organization's written password policy, read from a TOML
+//! file like:
+//!
+//! ```toml
+//! [default]
+//! min_length = 12
+//! require_uppercase = true
+//! require_digit = true
+//! banned_substrings = ["password", "acme"]
+//! no_username = true
+//! max_repeated_characters = 3
+//!
+//! # Applies to this group and the groups inside it
+//! [groups."Root/Banking"]
+//! min_length = 20
+//! ```
+use crate::entries::Entry;
+use crate::error::Error;
+use crate::report::Report;
+use crate::{Check, Destination, write_to};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Rules a password must follow. Rules that aren't set aren't checked, and in a group
+/// override, fall back to the default rules.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rules {
+    pub min_length: Option<usize>,
+    pub require_lowercase: Option<bool>,
+    pub require_uppercase: Option<bool>,
+    pub require_digit: Option<bool>,
+    pub require_symbol: Option<bool>,
+    /// Matched case-insensitively
+    pub banned_substrings: Option<Vec<String>>,
+    /// Whether passwords may contain the entry's username
+    pub no_username: Option<bool>,
+    /// The most times a character may appear in a row
+    pub max_repeated_characters: Option<usize>,
+}
+
+impl Rules {
+    /// These rules, with any rules set in `overrides` taking precedence
+    fn overridden_by(&self, overrides: &Rules) -> Rules {
+        Rules {
+            min_length: overrides.min_length.or(self.min_length),
+            require_lowercase: overrides.require_lowercase.or(self.require_lowercase),
+            require_uppercase: overrides.require_uppercase.or(self.require_uppercase),
+            require_digit: overrides.require_digit.or(self.require_digit),
+            require_symbol: overrides.require_symbol.or(self.require_symbol),
+            banned_substrings: overrides
+                .banned_substrings
+                .clone()
+                .or_else(|| self.banned_substrings.clone()),
+            no_username: overrides.no_username.or(self.no_username),
+            max_repeated_characters: overrides
+                .max_repeated_characters
+                .or(self.max_repeated_characters),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    #[serde(default)]
+    pub default: Rules,
+    /// Overrides for entries in a group (like "Root/Banking") or any group inside it
+    #[serde(default)]
+    pub groups: BTreeMap<String, Rules>,
+}
+
+impl Policy {
+    /// The rules for an entry in the given group. When several group overrides apply, the
+    /// most specific one wins.
+    pub fn rules_for(&self, group: &str) -> Rules {
+        let mut matching: Vec<(&String, &Rules)> = self
+            .groups
+            .iter()
+            .filter(|(path, _)| {
+                group == path.as_str()
+                    || group
+                        .strip_prefix(path.as_str())
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .collect();
+        matching.sort_by_key(|(path, _)| path.len());
+        matching
+            .into_iter()
+            .fold(self.default.clone(), |rules, (_, overrides)| {
+                rules.overridden_by(overrides)
+            })
+    }
+}
+
+pub fn read_policy(file_path: PathBuf) -> Result<Policy, Error> {
+    let contents = match fs::read_to_string(&file_path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            return Err(Error::Open {
+                path: file_path,
+                source: e,
+            });
+        }
+    };
+    toml::from_str(&contents).map_err(|e| Error::Parse(format!("{}: {}", file_path.display(), e)))
+}
+
+/// Every way the entry's password breaks the rules, in words. Never includes the password.
+pub fn policy_violations(entry: &Entry, rules: &Rules) -> Vec<String> {
+    let password = &entry.pass;
+    let mut violations: Vec<String> = vec![];
+    if let Some(min_length) = rules.min_length
+        && password.chars().count() < min_length
+    {
+        violations.push(format!("shorter than {} characters", min_length));
+    }
+    let classes: [CharacterClass; 4] = [
+        (
+            rules.require_lowercase,
+            |c| c.is_lowercase(),
+            "lowercase letter",
+        ),
+        (
+            rules.require_uppercase,
+            |c| c.is_uppercase(),
+            "uppercase letter",
+        ),
+        (rules.require_digit, |c| c.is_ascii_digit(), "digit"),
+        (rules.require_symbol, is_symbol, "symbol"),
+    ];
+    for (required, is_in_class, name) in classes {
+        if required == Some(true) && !password.chars().any(is_in_class) {
+            violations.push(format!("has no {}", name));
+        }
+    }
+    let lowercase_password = password.to_lowercase();
+    for banned in rules.banned_substrings.iter().flatten() {
+        if !banned.is_empty() && lowercase_password.contains(&banned.to_lowercase()) {
+            violations.push(format!("contains \"{}\"", banned));
+        }
+    }
+    if rules.no_username == Some(true)
+        && !entry.username.is_empty()
+        && lowercase_password.contains(&entry.username.to_lowercase())
+    {
+        violations.push("contains the username".to_string());
+    }
+    if let Some(max_repeated) = rules.max_repeated_characters
+        && longest_run(password) > max_repeated
+    {
+        violations.push(format!(
+            "repeats a character more than {} times in a row",
+            max_repeated
+        ));
+    }
+    violations
+}
+
+/// Whether a rule requires the class, how to recognize its characters, and its name
+type CharacterClass = (Option<bool>, fn(char) -> bool, &'static str);
+
+fn is_symbol(c: char) -> bool {
+    !c.is_alphanumeric() && !c.is_whitespace()
+}
+
+/// Length of the longest run of one character, e.g. 3 for "abccc"
+fn longest_run(password: &str) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    let mut previous: Option<char> = None;
+    for c in password.chars() {
+        current = if previous == Some(c) { current + 1 } else { 1 };
+        longest = longest.max(current);
+        previous = Some(c);
+    }
+    longest
+}
+
+/// Finds entries whose passwords break the policy for their group, along with how
+pub fn find_policy_violations(entries: &[Entry], policy: &Policy) -> Vec<(Entry, Vec<String>)> {
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let violations = policy_violations(entry, &policy.rules_for(&entry.group));
+            if violations.is_empty() {
+                None
+            } else {
+                Some((entry.clone(), violations))
+            }
+        })
+        .collect()
+}
+
+pub fn present_policy_violations(report: &Report, output_dest: &Destination) -> Result<(), Error> {
+    if report.count(Check::Policy) == 0 {
+        return write_to(
+            output_dest,
+            "\nAll of your passwords follow your password policy.",
+        );
+    }
+    write_to(
+        output_dest,
+        "\nThe following entries have passwords that break your password policy:",
+    )?;
+    for finding in report.findings_for(Check::Policy) {
+        write_to(
+            output_dest,
+            format!(
+                "   - {}: {}",
+                finding.entry,
+                finding.detail.as_deref().unwrap_or("")
+            ),
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,909 @@
+use crate::allowlist::Allowlist;
+use crate::entries::Entry;
+use crate::error::Error;
+use crate::patterns::PatternFamily;
+use crate::tags::weight_of_tags;
+use crate::{Check, CrackTimes, WeakPassword, shared_account_domain};
+use chrono::NaiveDateTime;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Bumped whenever the shape of the JSON report changes in a way that could break consumers
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// How to print results
+#[derive(Debug, PartialEq, Eq, Clone, Copy, clap::ValueEnum)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+    Markdown,
+    Html,
+}
+
+/// What a report says about an entry. Deliberately leaves out the password and its digest, so
+/// that reports are safe to store and share.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EntrySummary {
+    pub title: String,
+    pub username: String,
+    pub url: String,
+    pub group: String,
+    pub uuid: String,
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Line of the CSV export the entry was read from, if it was read from one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<u64>,
+}
+
+impl From<&Entry> for EntrySummary {
+    fn from(entry: &Entry) -> Self {
+        EntrySummary {
+            title: entry.title.clone(),
+            username: entry.username.clone(),
+            url: entry.url.clone(),
+            group: entry.group.clone(),
+            uuid: entry.uuid.clone(),
+            source: entry.source.clone(),
+            field: entry.field.clone(),
+            tags: entry.tags.clone(),
+            line: entry.line,
+        }
+    }
+}
+
+impl EntrySummary {
+    /// The entry itself, for a finding about one of its custom fields
+    fn without_field(&self) -> EntrySummary {
+        EntrySummary {
+            field: None,
+            ..self.clone()
+        }
+    }
+}
+
+/// What identifies an entry, whether it's a full [`Entry`] or an [`EntrySummary`] from a report
+pub trait EntryIdentity {
+    fn title(&self) -> &str;
+    fn username(&self) -> &str;
+    fn url(&self) -> &str;
+    /// Empty when the source (e.g. a CSV export) doesn't provide one
+    fn uuid(&self) -> &str;
+    fn field(&self) -> Option<&str>;
+    /// Path of the database the entry was read from
+    fn source(&self) -> &str;
+}
+
+impl EntryIdentity for Entry {
+    fn title(&self) -> &str {
+        &self.title
+    }
+    fn username(&self) -> &str {
+        &self.username
+    }
+    fn url(&self) -> &str {
+        &self.url
+    }
+    fn uuid(&self) -> &str {
+        &self.uuid
+    }
+    fn field(&self) -> Option<&str> {
+        self.field.as_deref()
+    }
+    fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl EntryIdentity for EntrySummary {
+    fn title(&self) -> &str {
+        &self.title
+    }
+    fn username(&self) -> &str {
+        &self.username
+    }
+    fn url(&self) -> &str {
+        &self.url
+    }
+    fn uuid(&self) -> &str {
+        &self.uuid
+    }
+    fn field(&self) -> Option<&str> {
+        self.field.as_deref()
+    }
+    fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl<T: EntryIdentity + ?Sized> EntryIdentity for &T {
+    fn title(&self) -> &str {
+        (**self).title()
+    }
+    fn username(&self) -> &str {
+        (**self).username()
+    }
+    fn url(&self) -> &str {
+        (**self).url()
+    }
+    fn uuid(&self) -> &str {
+        (**self).uuid()
+    }
+    fn field(&self) -> Option<&str> {
+        (**self).field()
+    }
+    fn source(&self) -> &str {
+        (**self).source()
+    }
+}
+
+/// Whether two entries (or versions of one) are the same entry. They're matched by UUID when
+/// both have one, so that renaming an entry doesn't make it a different one, and otherwise by
+/// title, username and URL.
+pub fn is_same_entry(a: &impl EntryIdentity, b: &impl EntryIdentity) -> bool {
+    let same_entry = if !a.uuid().is_empty() && !b.uuid().is_empty() {
+        a.uuid() == b.uuid()
+    } else {
+        a.title() == b.title() && a.username() == b.username() && a.url() == b.url()
+    };
+    same_entry && a.field() == b.field()
+}
+
+impl std::fmt::Display for EntrySummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if !self.title.is_empty() {
+            write!(f, "{} on {}", self.username, self.title)?;
+        } else if !self.url.is_empty() {
+            write!(f, "{} for {}", self.username, self.url)?;
+        } else {
+            write!(f, "{}", self.username)?;
+        }
+        if let Some(field) = &self.field {
+            write!(f, " ({} field)", field)?;
+        }
+        if !self.tags.is_empty() {
+            write!(f, " [{}]", self.tags.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// One problem with one entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    pub check: Check,
+    pub entry: EntrySummary,
+    /// Entries sharing a password get the same group number
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_group: Option<usize>,
+    /// For duplicates, how many entries share the password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+    /// For duplicates, set when every entry sharing the password is the same account (the
+    /// same username on the same site), rather than the password being re-used across sites
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub same_account: bool,
+    /// For weak passwords, zxcvbn's score from 0 to 4
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<u8>,
+    /// For weak passwords, zxcvbn's estimate of how many guesses it would take to find it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guesses: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guesses_log10: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crack_times: Option<CrackTimes>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+    /// A human-readable explanation, e.g. when an old password was last changed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Set on old passwords that are also breached or re-used
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub high_priority: bool,
+    /// When the password was last changed (old and previous passwords), or when the entry
+    /// expires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDateTime>,
+    /// For current passwords that were used before, the entries that used to have them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub previously_used_by: Vec<EntrySummary>,
+}
+
+impl Finding {
+    /// A one-line explanation of the finding, for formats that don't have a column per field
+    pub fn describe(&self) -> String {
+        let mut parts: Vec<String> = vec![];
+        if self.high_priority {
+            parts.push("HIGH PRIORITY".to_string());
+        }
+        if let (Some(group), Some(count)) = (self.duplicate_group, self.count) {
+            let kind = match self.check {
+                Check::Similar => "similar group",
+                Check::Pattern => "pattern family",
+                _ => "duplicate group",
+            };
+            parts.push(format!("{} {} ({} entries)", kind, group, count));
+        }
+        if let Some(score) = self.score {
+            parts.push(format!("score {}/4", score));
+        }
+        if let Some(crack_times) = &self.crack_times {
+            parts.push(format!(
+                "guessed in {} online (throttled), {} offline (fast hash)",
+                crack_times.online_throttled, crack_times.offline_fast_hash
+            ));
+        }
+        if let Some(warning) = &self.warning {
+            parts.push(warning.to_string());
+        }
+        parts.extend(self.suggestions.iter().cloned());
+        if let Some(detail) = &self.detail {
+            parts.push(detail.to_string());
+        }
+        parts.join("; ")
+    }
+
+    fn new(check: Check, entry: &Entry) -> Self {
+        Finding {
+            check,
+            entry: EntrySummary::from(entry),
+            duplicate_group: None,
+            count: None,
+            same_account: false,
+            score: None,
+            guesses: None,
+            guesses_log10: None,
+            crack_times: None,
+            warning: None,
+            suggestions: vec![],
+            detail: None,
+            high_priority: false,
+            date: None,
+            previously_used_by: vec![],
+        }
+    }
+
+    fn with_detail<StringLike: Into<String>>(mut self, detail: StringLike) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+/// Everything medic found, in a form that can be serialized
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Report {
+    pub schema_version: u32,
+    pub medic_version: String,
+    pub entries_checked: usize,
+    /// The checks that were run, whether or not they found anything
+    #[serde(default)]
+    pub checks: Vec<Check>,
+    /// Weights given to tags with `--tag-weight`, which count towards the health score
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "serialize_sorted"
+    )]
+    pub tag_weights: HashMap<String, u32>,
+    /// Total weight of all checked entries. `None` if every entry weighs 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entries_weight: Option<u32>,
+    pub findings: Vec<Finding>,
+    /// Findings that an allowlist says are acceptable. They don't count towards the summary,
+    /// health score or `--fail-on`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suppressed: Vec<SuppressedFinding>,
+}
+
+/// A finding, and the allowlist's reason for accepting it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuppressedFinding {
+    pub finding: Finding,
+    pub reason: String,
+}
+
+/// Counts that sum up a report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub entries_checked: usize,
+    /// Entries with at least one finding
+    pub entries_with_findings: usize,
+    pub findings: usize,
+    /// Number of findings for each check that was run
+    pub counts: BTreeMap<Check, usize>,
+    /// See `Report::health_score`
+    pub health_score: u8,
+}
+
+/// Writes a map with its keys in order, so that saved reports can be compared
+fn serialize_sorted<S: serde::Serializer>(
+    map: &HashMap<String, u32>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+/// Sorts a group of entries by how they're displayed, so that reports come out the same from
+/// one run to the next
+fn in_display_order(group: &[Entry]) -> Vec<&Entry> {
+    let mut group: Vec<&Entry> = group.iter().collect();
+    group.sort_by_cached_key(|entry| (entry.to_string(), entry.source.clone(), entry.uuid.clone()));
+    group
+}
+
+/// Biggest groups first, then by their (already sorted) entries
+fn group_order(group: &[&Entry]) -> (std::cmp::Reverse<usize>, Vec<String>) {
+    (
+        std::cmp::Reverse(group.len()),
+        group.iter().map(|entry| entry.to_string()).collect(),
+    )
+}
+
+/// Duplicates within one account are much less of a worry than re-use across sites
+fn finding_severity(finding: &Finding) -> u32 {
+    match finding.check {
+        Check::Duplicate if finding.same_account => 2,
+        check => severity(check),
+    }
+}
+
+/// How much a finding of each kind hurts the health score, out of 10. An entry with several
+/// findings counts only its most severe one.
+fn severity(check: Check) -> u32 {
+    match check {
+        Check::Breached => 10,
+        Check::Duplicate => 8,
+        Check::Similar => 6,
+        Check::Pattern => 6,
+        Check::History => 6,
+        Check::Weak => 5,
+        Check::Context => 5,
+        Check::Notes => 5,
+        Check::Policy => 4,
+        Check::Stale => 3,
+        Check::Expired => 3,
+    }
+}
+
+impl Report {
+    pub fn new(entries_checked: usize) -> Self {
+        Report {
+            schema_version: SCHEMA_VERSION,
+            medic_version: env!("CARGO_PKG_VERSION").to_string(),
+            entries_checked,
+            checks: vec![],
+            tag_weights: HashMap::new(),
+            entries_weight: None,
+            findings: vec![],
+            suppressed: vec![],
+        }
+    }
+
+    /// Moves the findings that `allowlist` accepts into `suppressed`. Duplicate and similar
+    /// passwords are only suppressed a whole group at a time, when every entry in the group is
+    /// allowed, so that an allowed entry sharing a password with an unexpected one still shows
+    /// up.
+    pub fn apply_allowlist(&mut self, allowlist: &Allowlist) {
+        let reasons: Vec<Option<String>> = self
+            .findings
+            .iter()
+            .map(|finding| allowlist.reason_for(finding).map(str::to_string))
+            .collect();
+        let group_allowed = |check: Check, group: usize| {
+            self.findings
+                .iter()
+                .zip(&reasons)
+                .filter(|(finding, _)| {
+                    finding.check == check && finding.duplicate_group == Some(group)
+                })
+                .all(|(_, reason)| reason.is_some())
+        };
+        let suppress: Vec<bool> = self
+            .findings
+            .iter()
+            .zip(&reasons)
+            .map(|(finding, reason)| {
+                reason.is_some()
+                    && match (finding.check, finding.duplicate_group) {
+                        (Check::Duplicate | Check::Similar, Some(group)) => {
+                            group_allowed(finding.check, group)
+                        }
+                        _ => true,
+                    }
+            })
+            .collect();
+        let findings = std::mem::take(&mut self.findings);
+        for ((finding, reason), suppress) in findings.into_iter().zip(reasons).zip(suppress) {
+            match reason {
+                Some(reason) if suppress => {
+                    self.suppressed.push(SuppressedFinding { finding, reason })
+                }
+                _ => self.findings.push(finding),
+            }
+        }
+    }
+
+    /// Makes findings for heavier entries (see `--tag-weight`) count for more in the health
+    /// score. `entries` should be the entries that were checked, without the custom fields
+    /// checked as secrets.
+    pub fn set_tag_weights(&mut self, entries: &[Entry], tag_weights: &HashMap<String, u32>) {
+        self.tag_weights = tag_weights.clone();
+        self.entries_weight = Some(
+            entries
+                .iter()
+                .map(|entry| weight_of_tags(&entry.tags, tag_weights))
+                .sum(),
+        );
+    }
+
+    /// Records that a check was run, even if it finds nothing
+    fn ran(&mut self, check: Check) {
+        if !self.checks.contains(&check) {
+            self.checks.push(check);
+        }
+    }
+
+    pub fn has_run(&self, check: Check) -> bool {
+        self.checks.contains(&check)
+    }
+
+    /// Findings of the given kind, in the order they were added
+    pub fn findings_for(&self, check: Check) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(move |finding| finding.check == check)
+    }
+
+    pub fn summary(&self) -> Summary {
+        let entries_with_findings: HashSet<EntrySummary> = self
+            .findings
+            .iter()
+            .map(|finding| finding.entry.without_field())
+            .collect();
+        Summary {
+            entries_checked: self.entries_checked,
+            entries_with_findings: entries_with_findings.len(),
+            findings: self.findings.len(),
+            counts: self
+                .checks
+                .iter()
+                .map(|check| (*check, self.count(*check)))
+                .collect(),
+            health_score: self.health_score(),
+        }
+    }
+
+    pub fn add_breached(&mut self, breached_entries: &[Entry]) {
+        self.ran(Check::Breached);
+        for entry in breached_entries {
+            self.findings.push(
+                Finding::new(Check::Breached, entry)
+                    .with_detail("password is on the list of breached passwords"),
+            );
+        }
+    }
+
+    pub fn add_breached_previous_passwords(&mut self, breached_previous_passwords: &[Entry]) {
+        self.ran(Check::History);
+        for entry in breached_previous_passwords {
+            let detail = match entry.modified {
+                Some(modified) => format!(
+                    "a previous password (from {}) is on the list of breached passwords",
+                    modified.date()
+                ),
+                None => "a previous password is on the list of breached passwords".to_string(),
+            };
+            let mut finding = Finding::new(Check::History, entry).with_detail(detail);
+            finding.date = entry.modified;
+            self.findings.push(finding);
+        }
+    }
+
+    pub fn add_reused_previous_passwords(&mut self, reused_entries: &[(Entry, Vec<Entry>)]) {
+        self.ran(Check::History);
+        for (entry, previous_owners) in reused_entries {
+            let descriptions: Vec<String> = previous_owners
+                .iter()
+                .map(|previous_owner| {
+                    if is_same_entry(previous_owner, entry) {
+                        "this same entry".to_string()
+                    } else {
+                        previous_owner.to_string()
+                    }
+                })
+                .collect();
+            let mut finding = Finding::new(Check::History, entry).with_detail(format!(
+                "password was previously used by {}",
+                descriptions.join(", ")
+            ));
+            finding.previously_used_by = previous_owners.iter().map(EntrySummary::from).collect();
+            self.findings.push(finding);
+        }
+    }
+
+    // Clippy told me "warning: parameter of type `HashMap` should be generalized over different hashers"
+    pub fn add_duplicates<S: ::std::hash::BuildHasher>(
+        &mut self,
+        digest_map: &HashMap<String, Vec<Entry>, S>,
+    ) {
+        self.ran(Check::Duplicate);
+        // Re-use across sites gets the lower group numbers, so it's listed first
+        let mut groups: Vec<(Vec<&Entry>, Option<String>)> = digest_map
+            .values()
+            .filter(|group| group.len() > 1)
+            .map(|group| (in_display_order(group), shared_account_domain(group)))
+            .collect();
+        groups.sort_by_cached_key(|(group, domain)| (domain.is_some(), group_order(group)));
+        for (i, (group, domain)) in groups.iter().enumerate() {
+            for entry in group {
+                let mut finding = Finding::new(Check::Duplicate, entry);
+                finding.duplicate_group = Some(i + 1);
+                finding.count = Some(group.len());
+                if let Some(domain) = domain {
+                    finding.same_account = true;
+                    finding = finding.with_detail(format!("same account on {}", domain));
+                }
+                self.findings.push(finding);
+            }
+        }
+    }
+
+    /// Each cluster of similar passwords gets a number, shared by its findings in
+    /// `duplicate_group`
+    pub fn add_similar(&mut self, clusters: &[Vec<Entry>]) {
+        self.ran(Check::Similar);
+        let mut clusters: Vec<Vec<&Entry>> = clusters
+            .iter()
+            .map(|cluster| in_display_order(cluster))
+            .collect();
+        clusters.sort_by_cached_key(|cluster| group_order(cluster));
+        for (i, cluster) in clusters.iter().enumerate() {
+            for entry in cluster {
+                let mut finding = Finding::new(Check::Similar, entry);
+                finding.duplicate_group = Some(i + 1);
+                finding.count = Some(cluster.len());
+                self.findings.push(finding);
+            }
+        }
+    }
+
+    /// Like similar passwords, each family gets a number, shared by its findings in
+    /// `duplicate_group`
+    pub fn add_pattern_families(&mut self, families: &[PatternFamily]) {
+        self.ran(Check::Pattern);
+        let mut families: Vec<(Vec<&Entry>, String)> = families
+            .iter()
+            .map(|family| (in_display_order(&family.entries), family.describe()))
+            .collect();
+        families.sort_by_cached_key(|(entries, _)| group_order(entries));
+        for (i, (entries, pattern)) in families.iter().enumerate() {
+            for entry in entries {
+                let mut finding = Finding::new(Check::Pattern, entry).with_detail(pattern.as_str());
+                finding.duplicate_group = Some(i + 1);
+                finding.count = Some(entries.len());
+                self.findings.push(finding);
+            }
+        }
+    }
+
+    pub fn add_weak(&mut self, weak_passwords: &[WeakPassword]) {
+        self.ran(Check::Weak);
+        for weak_password in weak_passwords {
+            let mut finding = Finding::new(Check::Weak, &weak_password.entry);
+            finding.score = Some(weak_password.score);
+            finding.guesses = Some(weak_password.guesses);
+            finding.guesses_log10 = Some(weak_password.guesses_log10);
+            finding.crack_times = Some(weak_password.crack_times.clone());
+            finding.warning = weak_password.warning.clone();
+            finding.suggestions = weak_password.suggestions.clone();
+            if !weak_password.banned_words.is_empty() {
+                finding = finding.with_detail(banned_words_detail(&weak_password.banned_words));
+            }
+            self.findings.push(finding);
+        }
+    }
+
+    pub fn add_stale(&mut self, stale_entries: &[Entry], compromised_entries: &[Entry]) {
+        self.ran(Check::Stale);
+        let now = chrono::Utc::now().naive_utc();
+        for entry in stale_entries {
+            let mut finding = Finding::new(Check::Stale, entry);
+            finding.date = entry.modified;
+            if let Some(modified) = entry.modified {
+                finding = finding.with_detail(format!(
+                    "password last changed {}, {} days ago",
+                    modified.date(),
+                    (now - modified).num_days()
+                ));
+            }
+            finding.high_priority = compromised_entries
+                .iter()
+                .any(|compromised| compromised.digest == entry.digest);
+            self.findings.push(finding);
+        }
+    }
+
+    pub fn add_expiring(&mut self, expired_entries: &[Entry], expiring_entries: &[Entry]) {
+        self.ran(Check::Expired);
+        for (entries, verb) in [(expired_entries, "expired"), (expiring_entries, "expires")] {
+            for entry in entries {
+                let mut finding = Finding::new(Check::Expired, entry);
+                finding.date = entry.expiry;
+                if let Some(expiry) = entry.expiry {
+                    finding = finding.with_detail(format!("{} {}", verb, expiry.date()));
+                }
+                self.findings.push(finding);
+            }
+        }
+    }
+
+    pub fn add_policy_violations(&mut self, violating_entries: &[(Entry, Vec<String>)]) {
+        self.ran(Check::Policy);
+        for (entry, violations) in violating_entries {
+            self.findings
+                .push(Finding::new(Check::Policy, entry).with_detail(violations.join("; ")));
+        }
+    }
+
+    pub fn add_entry_details_in_passwords(&mut self, entries: &[(Entry, Vec<String>)]) {
+        self.ran(Check::Context);
+        for (entry, found) in entries {
+            self.findings
+                .push(Finding::new(Check::Context, entry).with_detail(found.join("; ")));
+        }
+    }
+
+    pub fn add_secrets_in_notes(&mut self, suspicious_entries: &[(Entry, String)]) {
+        self.ran(Check::Notes);
+        for (entry, reason) in suspicious_entries {
+            self.findings
+                .push(Finding::new(Check::Notes, entry).with_detail(reason.as_str()));
+        }
+    }
+
+    /// How many findings of the given kind there are
+    pub fn count(&self, check: Check) -> usize {
+        self.findings_for(check).count()
+    }
+
+    /// From 100 (nothing found) down to 0. Each entry with findings takes away its share of
+    /// the vault, scaled by the severity of its worst finding and by its tag weight.
+    pub fn health_score(&self) -> u8 {
+        let entries_weight = self.entries_weight.unwrap_or(self.entries_checked as u32);
+        if entries_weight == 0 {
+            return 100;
+        }
+        let mut worst_severities: HashMap<EntrySummary, u32> = HashMap::new();
+        for finding in &self.findings {
+            let worst = worst_severities
+                .entry(finding.entry.without_field())
+                .or_default();
+            *worst = (*worst).max(finding_severity(finding));
+        }
+        let penalty: u32 = worst_severities
+            .iter()
+            .map(|(entry, severity)| weight_of_tags(&entry.tags, &self.tag_weights) * severity)
+            .sum();
+        let score = 100.0 - f64::from(penalty) * 10.0 / f64::from(entries_weight);
+        score.clamp(0.0, 100.0).round() as u8
+    }
+
+    /// The report, followed by its summary
+    pub fn to_json(&self) -> Result<String, Error> {
+        #[derive(Serialize)]
+        struct ReportWithSummary<'a> {
+            #[serde(flatten)]
+            report: &'a Report,
+            summary: Summary,
+        }
+        Ok(serde_json::to_string_pretty(&ReportWithSummary {
+            report: self,
+            summary: self.summary(),
+        })?)
+    }
+
+    /// One row per finding
+    pub fn to_csv(&self) -> Result<String, Error> {
+        findings_to_csv(&[], self.findings.iter().map(|finding| (vec![], finding)))
+    }
+
+    /// Reads a report previously written with `--format json`
+    pub fn from_json(json: &str) -> Result<Report, Error> {
+        let report: Report = serde_json::from_str(json)?;
+        if report.schema_version > SCHEMA_VERSION {
+            return Err(Error::Parse(format!(
+                "report uses schema version {}, but this version of medic only understands up to version {}",
+                report.schema_version, SCHEMA_VERSION
+            )));
+        }
+        Ok(report)
+    }
+
+    /// A single, self-contained HTML page
+    pub fn to_html(&self) -> String {
+        crate::html::render(self)
+    }
+
+    /// A section per check, each with a table of findings
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!(
+            "# Medic report\n\nChecked {} entries, found {} problems.\n",
+            self.entries_checked,
+            self.findings.len()
+        );
+        for check in Check::value_variants() {
+            let findings: Vec<&Finding> = self.findings_for(*check).collect();
+            if findings.is_empty() {
+                continue;
+            }
+            markdown.push_str(&format!(
+                "\n## {} ({})\n\n| Title | Username | URL | Group | Details |\n|---|---|---|---|---|\n",
+                section_title(*check),
+                findings.len()
+            ));
+            for finding in findings {
+                let entry = &finding.entry;
+                let title = match &entry.field {
+                    Some(field) => format!("{} ({} field)", entry.title, field),
+                    None => entry.title.clone(),
+                };
+                markdown.push_str(&format!(
+                    "| {} | {} | {} | {} | {} |\n",
+                    escape_markdown_cell(&title),
+                    escape_markdown_cell(&entry.username),
+                    escape_markdown_cell(&entry.url),
+                    escape_markdown_cell(&entry.group),
+                    escape_markdown_cell(&finding.describe()),
+                ));
+            }
+        }
+        if !self.suppressed.is_empty() {
+            markdown.push_str(&format!(
+                "\n## Allowed findings ({})\n\n| Check | Title | Username | URL | Reason |\n|---|---|---|---|---|\n",
+                self.suppressed.len()
+            ));
+            for suppressed in &self.suppressed {
+                let entry = &suppressed.finding.entry;
+                markdown.push_str(&format!(
+                    "| {} | {} | {} | {} | {} |\n",
+                    section_title(suppressed.finding.check),
+                    escape_markdown_cell(&entry.title),
+                    escape_markdown_cell(&entry.username),
+                    escape_markdown_cell(&entry.url),
+                    escape_markdown_cell(&suppressed.reason),
+                ));
+            }
+        }
+        let summary = self.summary();
+        markdown.push_str(&format!(
+            "\n## Summary\n\nHealth score: **{}/100**. {} of {} entries have problems.\n\n| Check | Findings |\n|---|---|\n",
+            summary.health_score, summary.entries_with_findings, summary.entries_checked
+        ));
+        for (check, count) in summary.counts {
+            markdown.push_str(&format!("| {} | {} |\n", section_title(check), count));
+        }
+        markdown
+    }
+}
+
+/// Writes a row per finding, with any extra columns (e.g. a diff's status) first
+pub(crate) fn findings_to_csv<'a>(
+    extra_headers: &[&str],
+    rows: impl IntoIterator<Item = (Vec<String>, &'a Finding)>,
+) -> Result<String, Error> {
+    let mut wtr = csv::Writer::from_writer(vec![]);
+    let headers = [
+        "Check",
+        "Title",
+        "Username",
+        "URL",
+        "Group",
+        "UUID",
+        "Source",
+        "Field",
+        "Tags",
+        "Duplicate Group",
+        "Count",
+        "Score",
+        "Guesses",
+        "Guesses Log10",
+        "Online Throttled Crack Time",
+        "Offline Fast Hash Crack Time",
+        "Warning",
+        "Suggestions",
+        "Detail",
+        "High Priority",
+    ];
+    wtr.write_record(extra_headers.iter().chain(headers.iter()))?;
+    for (extra_columns, finding) in rows {
+        let entry = &finding.entry;
+        wtr.write_record(
+            extra_columns.into_iter().chain([
+                check_name(finding.check),
+                entry.title.clone(),
+                entry.username.clone(),
+                entry.url.clone(),
+                entry.group.clone(),
+                entry.uuid.clone(),
+                entry.source.clone(),
+                entry.field.clone().unwrap_or_default(),
+                entry.tags.join(";"),
+                optional_to_string(finding.duplicate_group),
+                optional_to_string(finding.count),
+                optional_to_string(finding.score),
+                optional_to_string(finding.guesses),
+                optional_to_string(finding.guesses_log10),
+                optional_to_string(
+                    finding
+                        .crack_times
+                        .as_ref()
+                        .map(|crack_times| &crack_times.online_throttled),
+                ),
+                optional_to_string(
+                    finding
+                        .crack_times
+                        .as_ref()
+                        .map(|crack_times| &crack_times.offline_fast_hash),
+                ),
+                finding.warning.clone().unwrap_or_default(),
+                finding.suggestions.join(" "),
+                finding.detail.clone().unwrap_or_default(),
+                finding.high_priority.to_string(),
+            ]),
+        )?;
+    }
+    let bytes = wtr.into_inner().map_err(|e| Error::Io(e.into_error()))?;
+    String::from_utf8(bytes).map_err(|e| Error::Parse(e.to_string()))
+}
+
+fn banned_words_detail(banned_words: &[String]) -> String {
+    let quoted: Vec<String> = banned_words
+        .iter()
+        .map(|word| format!("\"{}\"", word))
+        .collect();
+    match quoted.len() {
+        1 => format!("contains the banned word {}", quoted[0]),
+        _ => format!("contains the banned words {}", quoted.join(", ")),
+    }
+}
+
+/// The name used for a check on the command line and in JSON reports
+pub(crate) fn check_name(check: Check) -> String {
+    check
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+pub(crate) fn section_title(check: Check) -> &'static str {
+    match check {
+        Check::Breached => "Breached passwords",
+        Check::Weak => "Weak passwords",
+        Check::Context => "Passwords built from entry details",
+        Check::Duplicate => "Duplicate passwords",
+        Check::Similar => "Similar passwords",
+        Check::Pattern => "Password pattern families",
+        Check::Stale => "Old passwords",
+        Check::Expired => "Expired and expiring entries",
+        Check::History => "Password history",
+        Check::Notes => "Secrets in notes",
+        Check::Policy => "Password policy violations",
+    }
+}
+
+fn optional_to_string<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Pipes would end the cell early, and newlines the row
+pub(crate) fn escape_markdown_cell(cell: &str) -> String {
+    cell.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
@@ -0,0 +1,138 @@
+use crate::entries::Entry;
+use crate::error::Error;
+use crate::report::Report;
+use crate::{Check, Destination, write_to};
+
+/// Labels that, followed by `:` or `=` in an entry's notes, usually introduce a secret
+const SECRET_LABELS: [&str; 10] = [
+    "password",
+    "passwd",
+    "pass",
+    "pwd",
+    "pin",
+    "secret",
+    "token",
+    "api key",
+    "recovery code",
+    "backup code",
+];
+
+/// Turns every custom field whose name matches one of `patterns` into an `Entry` of its own,
+/// so that its value can be run through the same checks as the entry's password. Patterns are
+/// case-insensitive and may use `*` (any run of characters) and `?` (any one character).
+pub fn custom_field_secrets(entries: &[Entry], patterns: &[String]) -> Vec<Entry> {
+    let mut field_entries = vec![];
+    for entry in entries {
+        for (name, value) in &entry.custom_fields {
+            if value.is_empty() || !patterns.iter().any(|p| matches_pattern(name, p)) {
+                continue;
+            }
+            field_entries.push(Entry {
+                pass: value.to_string(),
+                digest: sha1_smol::Sha1::from(value)
+                    .digest()
+                    .to_string()
+                    .to_uppercase(),
+                history: vec![],
+                custom_fields: vec![],
+                field: Some(name.to_string()),
+                ..entry.clone()
+            });
+        }
+    }
+    field_entries
+}
+
+fn matches_pattern(name: &str, pattern: &str) -> bool {
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    matches_glob(&name, &pattern)
+}
+
+fn matches_glob(name: &[char], pattern: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|i| matches_glob(&name[i..], rest)),
+        Some(('?', rest)) => !name.is_empty() && matches_glob(&name[1..], rest),
+        Some((c, rest)) => name.first() == Some(c) && matches_glob(&name[1..], rest),
+    }
+}
+
+/// Looks through each entry's notes for things that look like passwords, returning each
+/// suspicious entry along with an explanation. The suspected secret itself is never included.
+pub fn find_secrets_in_notes(entries: &[Entry]) -> Vec<(Entry, String)> {
+    let mut suspicious_entries = vec![];
+    for entry in entries {
+        if let Some(reason) = describe_secret_in_notes(entry) {
+            suspicious_entries.push((entry.clone(), reason));
+        }
+    }
+    suspicious_entries
+}
+
+fn describe_secret_in_notes(entry: &Entry) -> Option<String> {
+    if entry.notes.is_empty() {
+        return None;
+    }
+    // Very short passwords turn up inside ordinary words too often to be worth flagging
+    if entry.pass.chars().count() >= 4 && entry.notes.contains(&entry.pass) {
+        return Some("notes contain this entry's password".to_string());
+    }
+    for line in entry.notes.lines() {
+        let line = line.trim().to_lowercase();
+        for label in SECRET_LABELS {
+            if let Some(rest) = line.strip_prefix(label) {
+                let rest = rest.trim_start();
+                if (rest.starts_with(':') || rest.starts_with('=')) && !rest[1..].trim().is_empty()
+                {
+                    return Some(format!("notes contain a secret labelled \"{}\"", label));
+                }
+            }
+        }
+    }
+    if entry.notes.split_whitespace().any(looks_like_password) {
+        return Some("notes contain a password-like string".to_string());
+    }
+    None
+}
+
+/// A word of at least 8 characters mixing at least three of: lowercase letters, uppercase
+/// letters, digits and symbols. URLs and email addresses are ignored, as is punctuation
+/// wrapped around the word (so "Username:" doesn't count).
+fn looks_like_password(word: &str) -> bool {
+    let word = word.trim_matches(|c: char| ".,;:!?()[]\"'".contains(c));
+    if word.chars().count() < 8 || word.contains("://") || word.contains('@') {
+        return false;
+    }
+    let character_classes = [
+        word.chars().any(|c| c.is_lowercase()),
+        word.chars().any(|c| c.is_uppercase()),
+        word.chars().any(|c| c.is_ascii_digit()),
+        word.chars().any(|c| !c.is_alphanumeric()),
+    ];
+    character_classes.iter().filter(|&&present| present).count() >= 3
+}
+
+pub fn present_secrets_in_notes(report: &Report, output_dest: &Destination) -> Result<(), Error> {
+    if report.count(Check::Notes) == 0 {
+        return write_to(
+            output_dest,
+            "\nI didn't find anything that looks like a password in your notes.",
+        );
+    }
+    write_to(
+        output_dest,
+        "\nThe following entries may have secrets stored in plain notes:",
+    )?;
+    for finding in report.findings_for(Check::Notes) {
+        write_to(
+            output_dest,
+            format!(
+                "   - {}: {}",
+                finding.entry,
+                finding.detail.as_deref().unwrap_or("")
+            ),
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,163 @@
+//! Passwords that aren't identical, but are close enough that knowing one gives the other away,
+//! like `Summer2023!` and `Summer2024!`, or `GitHub-Tr0ub4dor` and `Twitter-Tr0ub4dor`.
+use crate::context;
+use crate::entries::Entry;
+use crate::error::Error;
+use crate::report::{EntrySummary, Report};
+use crate::{Check, Destination, write_to};
+use std::collections::BTreeMap;
+
+/// Passwords shorter than this are compared by their normalized forms only, since a couple of
+/// edits to a short password can turn it into just about anything
+const MIN_EDIT_DISTANCE_LENGTH: usize = 8;
+
+/// How many single-character edits apart two passwords can be and still count as similar
+const MAX_EDIT_DISTANCE: usize = 2;
+
+/// Normalized forms need at least this many letters to be worth comparing, so that e.g. PINs
+/// aren't all similar to each other
+const MIN_NORMALIZED_LETTERS: usize = 4;
+
+/// Lowercases the password and swaps out anything it shares with the entry's title, username
+/// or domain (see `context::user_inputs`) for `*`, so that passwords built from the site's name
+/// can be compared across sites.
+fn without_site_name(entry: &Entry) -> String {
+    let mut password = entry.pass.to_lowercase();
+    let mut inputs = context::user_inputs(entry, &[]);
+    // Longest first, so that "northwindbank" is replaced before "northwind" gets a chance
+    inputs.sort_by_key(|input| std::cmp::Reverse(input.chars().count()));
+    for input in inputs {
+        password = password.replace(&input, "*");
+    }
+    password
+}
+
+/// Collapses every run of digits to a single `0`, so that passwords that only differ by a
+/// year or a counter (trailing or not) normalize to the same thing
+fn normalize(password: &str) -> String {
+    let mut normalized = String::new();
+    for c in password.chars() {
+        if c.is_ascii_digit() {
+            if !normalized.ends_with('0') {
+                normalized.push('0');
+            }
+        } else {
+            normalized.push(c);
+        }
+    }
+    normalized
+}
+
+/// Levenshtein distance, counting characters rather than bytes
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut previous_row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.iter().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous_row[j] + usize::from(a_char != b_char);
+            row.push(substitution.min(previous_row[j + 1] + 1).min(row[j] + 1));
+        }
+        previous_row = row;
+    }
+    previous_row[b.len()]
+}
+
+fn has_enough_letters(normalized: &str) -> bool {
+    normalized.chars().filter(|c| c.is_alphabetic()).count() >= MIN_NORMALIZED_LETTERS
+}
+
+/// Symmetric, so that clusters don't depend on the order entries come in
+fn are_similar(a: &(String, String), b: &(String, String)) -> bool {
+    let ((a_password, a_normalized), (b_password, b_normalized)) = (a, b);
+    if !has_enough_letters(a_normalized) || !has_enough_letters(b_normalized) {
+        return false;
+    }
+    a_normalized == b_normalized
+        || (a_password.chars().count().min(b_password.chars().count()) >= MIN_EDIT_DISTANCE_LENGTH
+            && edit_distance(a_password, b_password) <= MAX_EDIT_DISTANCE)
+}
+
+/// Follows `parents` up to the root of `i`'s cluster
+fn find_root(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    parents[i] = root;
+    root
+}
+
+/// Groups entries whose passwords are similar but not all identical. Similarity is
+/// transitive, so if A is similar to B and B to C, all three end up in the same cluster.
+/// Entries with identical passwords are in the same cluster too, but it takes at least two
+/// different passwords to make one.
+pub fn find_similar_passwords(entries: &[Entry]) -> Vec<Vec<Entry>> {
+    let entries: Vec<&Entry> = entries
+        .iter()
+        .filter(|entry| !entry.pass.is_empty())
+        .collect();
+    let forms: Vec<(String, String)> = entries
+        .iter()
+        .map(|entry| {
+            let password = without_site_name(entry);
+            let normalized = normalize(&password);
+            (password, normalized)
+        })
+        .collect();
+
+    let mut parents: Vec<usize> = (0..entries.len()).collect();
+    for i in 0..entries.len() {
+        for j in (i + 1)..entries.len() {
+            if entries[i].digest == entries[j].digest || are_similar(&forms[i], &forms[j]) {
+                let (root_i, root_j) = (find_root(&mut parents, i), find_root(&mut parents, j));
+                // The earliest entry is always the root, so clusters can be ordered by it
+                parents[root_i.max(root_j)] = root_i.min(root_j);
+            }
+        }
+    }
+
+    let mut clusters: BTreeMap<usize, Vec<Entry>> = BTreeMap::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let root = find_root(&mut parents, i);
+        clusters.entry(root).or_default().push((*entry).clone());
+    }
+    clusters
+        .into_values()
+        .filter(|cluster| {
+            cluster
+                .iter()
+                .any(|entry| entry.digest != cluster[0].digest)
+        })
+        .collect()
+}
+
+pub fn present_similar_entries(report: &Report, output_dest: &Destination) -> Result<(), Error> {
+    let mut clusters: BTreeMap<usize, Vec<&EntrySummary>> = BTreeMap::new();
+    for finding in report.findings_for(Check::Similar) {
+        clusters
+            .entry(finding.duplicate_group.unwrap_or_default())
+            .or_default()
+            .push(&finding.entry);
+    }
+    if clusters.is_empty() {
+        return write_to(
+            output_dest,
+            "\nNone of your passwords are similar to each other.",
+        );
+    }
+    for cluster in clusters.values() {
+        write_to(
+            output_dest,
+            "\nThe following entries have similar passwords:",
+        )?;
+        for entry in cluster {
+            write_to(output_dest, format!("   - {}", entry))?;
+        }
+    }
+    write_to(
+        output_dest,
+        "\nIf one of these passwords leaks, the others are easy to guess from it. Recommend you change them so that they have nothing in common.\n--------------------------------\n",
+    )
+}
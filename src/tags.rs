@@ -0,0 +1,53 @@
+use crate::entries::Entry;
+use std::collections::HashMap;
+
+/// Tag names are compared case-insensitively, so `Prod` and `prod` are the same tag.
+fn has_tag(entry: &Entry, tag: &str) -> bool {
+    entry.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+}
+
+/// Keeps only the entries that have at least one of `tags`. If `tags` is empty, all entries
+/// are kept.
+pub fn filter_by_tags(entries: Vec<Entry>, tags: &[String]) -> Vec<Entry> {
+    if tags.is_empty() {
+        return entries;
+    }
+    entries
+        .into_iter()
+        .filter(|entry| tags.iter().any(|tag| has_tag(entry, tag)))
+        .collect()
+}
+
+/// Parses a `TAG=WEIGHT` pair, as given to `--tag-weight`
+pub fn parse_tag_weight(tag_weight: &str) -> Result<(String, u32), String> {
+    match tag_weight.rsplit_once('=') {
+        Some((tag, weight)) if !tag.is_empty() => match weight.parse::<u32>() {
+            Ok(weight) => Ok((tag.to_lowercase(), weight)),
+            Err(e) => Err(format!("invalid weight \"{}\": {}", weight, e)),
+        },
+        _ => Err(format!("expected TAG=WEIGHT, got \"{}\"", tag_weight)),
+    }
+}
+
+/// An entry's weight is the highest weight of any of its tags, or 1 if none of its tags
+/// have been given a weight.
+pub fn tag_weight(entry: &Entry, tag_weights: &HashMap<String, u32>) -> u32 {
+    weight_of_tags(&entry.tags, tag_weights)
+}
+
+pub(crate) fn weight_of_tags<S: ::std::hash::BuildHasher>(
+    tags: &[String],
+    tag_weights: &HashMap<String, u32, S>,
+) -> u32 {
+    tags.iter()
+        .filter_map(|tag| tag_weights.get(&tag.to_lowercase()))
+        .copied()
+        .max()
+        .unwrap_or(1)
+}
+
+/// Sorts entries so that those with the heaviest tags come first. The sort is stable, so
+/// entries of equal weight keep their order.
+pub fn sort_by_tag_weight(entries: &mut [Entry], tag_weights: &HashMap<String, u32>) {
+    entries.sort_by_key(|entry| std::cmp::Reverse(tag_weight(entry, tag_weights)));
+}
@@ -155,7 +155,15 @@ mod integration_tests {
     }
 
     // Test reading a CSV file (exported KeePass database)
-    fn make_test_entries_from_csv_export() -> Option<Vec<Entry>> {
+    fn make_test_entries_from_keepassxc_csv_export() -> Vec<Entry> {
+        get_entries(
+            PathBuf::from("tests/test-files/csv_exports/keepassxc_csv_export.csv"),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn make_test_entries_from_csv_export() -> Result<Vec<Entry>, Error> {
         let keepass_db_file_path = PathBuf::from("tests/test-files/csv_exports/csv_export.csv");
         get_entries(keepass_db_file_path, None)
     }
@@ -166,4 +174,1308 @@ mod integration_tests {
         let breached_entries = check_database_online(&entries);
         assert_eq!(breached_entries.unwrap().len(), 3); // there are 3 breached passwords in this test file
     }
+
+    #[test]
+    fn can_read_uuids_and_timestamps_from_keepass_database() {
+        let entries = make_test_entries_from_keepass_database_not_requiring_keyfile();
+        let github = entries.iter().find(|e| e.title == "GitHub").unwrap();
+        assert_eq!(github.uuid, "b1f95f2d-9001-4b0d-b6fb-b9b5fbf32a63");
+        assert_eq!(
+            github.modified.unwrap().to_string(),
+            "2019-10-17 18:50:28".to_string()
+        );
+        assert!(github.created.is_some());
+        assert!(github.accessed.is_some());
+        assert!(!github.expires);
+    }
+
+    #[test]
+    fn can_read_timestamps_from_keepassxc_csv_export() {
+        let entries = make_test_entries_from_keepassxc_csv_export();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[0].modified.unwrap().to_string(),
+            "2021-03-04 05:06:07".to_string()
+        );
+        assert_eq!(
+            entries[0].created.unwrap().to_string(),
+            "2019-10-17 18:50:28".to_string()
+        );
+        assert!(entries[0].uuid.is_empty());
+    }
+
+    #[test]
+    fn can_find_stale_entries_sorted_oldest_first() {
+        let entries = make_test_entries_from_keepass_4_database_requiring_keyfile();
+        let stale_entries = find_stale_entries(&entries, 0);
+        assert_eq!(stale_entries.len(), entries.len());
+        assert_eq!(stale_entries[0].title, "4.0 db test entry");
+        assert!(
+            stale_entries
+                .windows(2)
+                .all(|pair| pair[0].modified <= pair[1].modified)
+        );
+
+        assert!(find_stale_entries(&entries, 1_000_000).is_empty());
+    }
+
+    #[test]
+    fn can_find_expired_and_soon_to_expire_entries() {
+        let now = chrono::Utc::now().naive_utc();
+        let make_entry = |title: &str, expires: bool, days_from_now: i64| Entry {
+            title: title.to_string(),
+            expires,
+            expiry: Some(now + chrono::Duration::days(days_from_now)),
+            ..Default::default()
+        };
+        let entries = vec![
+            make_entry("expires next week", true, 7),
+            make_entry("expired last year", true, -365),
+            make_entry("expired yesterday", true, -1),
+            make_entry("expires next year", true, 365),
+            make_entry("never expires", false, -30),
+        ];
+        let (expired_entries, expiring_entries) = find_expiring_entries(&entries, 30);
+        assert_eq!(expired_entries.len(), 2);
+        assert_eq!(expired_entries[0].title, "expired last year");
+        assert_eq!(expiring_entries.len(), 1);
+        assert_eq!(expiring_entries[0].title, "expires next week");
+    }
+
+    fn make_test_entry(title: &str, uuid: &str, pass: &str) -> Entry {
+        Entry {
+            title: title.to_string(),
+            uuid: uuid.to_string(),
+            pass: pass.to_string(),
+            digest: sha1_smol::Sha1::from(pass)
+                .digest()
+                .to_string()
+                .to_uppercase(),
+            ..Default::default()
+        }
+    }
+
+    fn make_test_login(title: &str, username: &str, url: &str, pass: &str) -> Entry {
+        Entry {
+            username: username.to_string(),
+            url: url.to_string(),
+            ..make_test_entry(title, "", pass)
+        }
+    }
+
+    #[test]
+    fn can_find_current_passwords_that_were_used_before() {
+        // Rotated back to an old password after changing it
+        let mut rotated_back = make_test_entry("bank", "1", "hunter2");
+        rotated_back.history = vec![
+            make_test_entry("bank", "1", "hunter2"),
+            make_test_entry("bank", "1", "correcthorse"),
+        ];
+        // Uses a password that another entry used to have
+        let mut borrower = make_test_entry("forum", "2", "tr0ub4dor");
+        borrower.history = vec![make_test_entry("forum", "2", "forum-pass")];
+        let mut lender = make_test_entry("email", "3", "a-new-password");
+        lender.history = vec![
+            make_test_entry("email", "3", "tr0ub4dor"),
+            make_test_entry("email", "3", "tr0ub4dor"),
+        ];
+        // Changed its title without changing its password
+        let mut renamed = make_test_entry("renamed", "4", "unchanged");
+        renamed.history = vec![make_test_entry("old name", "4", "unchanged")];
+
+        let entries = vec![rotated_back, borrower, lender, renamed];
+        assert_eq!(history::previous_passwords(&entries).len(), 4);
+
+        let reused = history::find_reused_previous_passwords(&entries);
+        assert_eq!(reused.len(), 2);
+        assert_eq!(reused[0].0.title, "bank");
+        assert_eq!(reused[0].1[0].title, "bank");
+        assert_eq!(reused[1].0.title, "forum");
+        assert_eq!(reused[1].1.len(), 1);
+        assert_eq!(reused[1].1[0].title, "email");
+    }
+
+    #[test]
+    fn can_check_previous_passwords_against_small_offline_list_of_cleartext_passwords() {
+        let mut entry = make_test_entry("GitHub", "1", "KqG2qXHkaV62VD8bmqviv8");
+        entry.history = vec![make_test_entry("GitHub", "1", "secret")];
+        let previous_passwords = history::previous_passwords(&[entry]);
+
+        let breached_previous_passwords = check_database_offline(
+            PathBuf::from("tests/test-files/cleartext_passwords.txt"),
+            &previous_passwords,
+            &VisibilityPreference::Hide,
+            BreachedPasswordState::Clear,
+        )
+        .unwrap();
+        assert_eq!(breached_previous_passwords.len(), 1);
+        assert_eq!(breached_previous_passwords[0].title, "GitHub");
+    }
+
+    #[test]
+    fn can_treat_matching_custom_fields_as_secrets() {
+        let mut entry = make_test_entry("AWS", "1", "KqG2qXHkaV62VD8bmqviv8");
+        entry.custom_fields = vec![
+            ("API Token".to_string(), "password2".to_string()),
+            ("Account ID".to_string(), "123456789012".to_string()),
+            ("PIN".to_string(), "1234".to_string()),
+        ];
+        let patterns = vec!["*token*".to_string(), "pin".to_string()];
+        let field_entries = secrets::custom_field_secrets(&[entry], &patterns);
+        assert_eq!(field_entries.len(), 2);
+        assert_eq!(field_entries[0].field, Some("API Token".to_string()));
+        assert_eq!(field_entries[0].pass, "password2");
+        assert_eq!(field_entries[0].to_string(), " on AWS (API Token field)");
+
+        let breached_entries = check_database_offline(
+            PathBuf::from("tests/test-files/cleartext_passwords.txt"),
+            &field_entries,
+            &VisibilityPreference::Hide,
+            BreachedPasswordState::Clear,
+        )
+        .unwrap();
+        assert_eq!(breached_entries.len(), 1);
+    }
+
+    #[test]
+    fn can_find_password_like_strings_in_notes() {
+        let mut own_password = make_test_entry("own password", "1", "hunter22");
+        own_password.notes = "in case I forget: hunter22".to_string();
+        let mut labelled = make_test_entry("labelled", "2", "x");
+        labelled.notes = "Security questions below\nPIN: 4821".to_string();
+        let mut password_like = make_test_entry("password-like", "3", "x");
+        password_like.notes = "old one was Tr0ub4dor&3 I think".to_string();
+        let mut harmless = make_test_entry("harmless", "4", "x");
+        harmless.notes = "Support: https://example.com/Help?id=42 or help@example.com".to_string();
+
+        let entries = vec![own_password, labelled, password_like, harmless];
+        let suspicious_entries = secrets::find_secrets_in_notes(&entries);
+        assert_eq!(suspicious_entries.len(), 3);
+        assert_eq!(
+            suspicious_entries[0].1,
+            "notes contain this entry's password"
+        );
+        assert_eq!(
+            suspicious_entries[1].1,
+            "notes contain a secret labelled \"pin\""
+        );
+        assert_eq!(suspicious_entries[2].0.title, "password-like");
+    }
+
+    #[test]
+    fn can_read_filter_and_weight_tags_from_keepassxc_csv_export() {
+        let entries = make_test_entries_from_keepassxc_csv_export();
+        assert_eq!(entries[0].tags, vec!["prod", "work"]);
+        assert_eq!(entries[0].to_string(), "testuser on GitHub [prod, work]");
+        assert!(entries[2].tags.is_empty());
+
+        let mut filtered_entries = tags::filter_by_tags(
+            entries.clone(),
+            &["Personal".to_string(), "prod".to_string()],
+        );
+        assert_eq!(filtered_entries.len(), 2);
+
+        let tag_weights = [tags::parse_tag_weight("personal=5").unwrap()]
+            .into_iter()
+            .collect();
+        tags::sort_by_tag_weight(&mut filtered_entries, &tag_weights);
+        assert_eq!(filtered_entries[0].title, "Twitter");
+        assert_eq!(filtered_entries[1].title, "GitHub");
+
+        assert!(tags::parse_tag_weight("prod").is_err());
+        assert!(tags::parse_tag_weight("prod=high").is_err());
+    }
+
+    #[test]
+    fn can_find_passwords_reused_across_databases() {
+        let mut entries = make_test_entries_from_keepass_database_not_requiring_keyfile();
+        assert_eq!(
+            entries[0].source,
+            "tests/test-files/v4/test_db_no_keyfile_v4.kdbx"
+        );
+        entries.append(&mut make_test_entries_from_keepassxc_csv_export());
+        let digest_map = make_digest_map(&entries).unwrap();
+
+        let cross_database_groups: Vec<&Vec<Entry>> = digest_map
+            .values()
+            .filter(|group| group.len() > 1 && is_cross_database(group))
+            .collect();
+        // GitHub and Twitter appear in both, and twitch's password was also used for YouTube
+        assert_eq!(cross_database_groups.len(), 3);
+        assert!(!is_cross_database(&entries[..2]));
+    }
+
+    #[test]
+    fn databases_with_the_same_file_name_are_told_apart() {
+        let dir = std::env::temp_dir().join(format!("medic-vaults-test-{}", std::process::id()));
+        let mut entries = vec![];
+        for engineer in ["alice", "bob"] {
+            let vault = dir.join(engineer).join("passwords.csv");
+            std::fs::create_dir_all(vault.parent().unwrap()).unwrap();
+            std::fs::copy(
+                "tests/test-files/csv_exports/keepassxc_csv_export.csv",
+                &vault,
+            )
+            .unwrap();
+            entries.append(&mut get_entries(vault, None).unwrap());
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_ne!(entries[0].source, entries[3].source);
+        assert!(entries[0].source.ends_with("passwords.csv"));
+        let digest_map = make_digest_map(&entries).unwrap();
+        let reused = &digest_map[&entries[0].digest];
+        assert_eq!(reused.len(), 2);
+        assert!(is_cross_database(reused));
+    }
+
+    #[test]
+    fn returns_errors_instead_of_panicking() {
+        let wrong_password = build_entries_from_keepass_db(
+            PathBuf::from("tests/test-files/v4/test_db_no_keyfile_v4.kdbx"),
+            "not the password".to_string(),
+            None,
+        );
+        assert!(matches!(wrong_password, Err(Error::Unlock(_))));
+
+        let missing_keyfile = build_entries_from_keepass_db(
+            PathBuf::from("tests/test-files/v4/version_4_test_db.kdbx"),
+            "password".to_string(),
+            Some(PathBuf::from("tests/test-files/v4/no_such_keyfile")),
+        );
+        assert!(matches!(missing_keyfile, Err(Error::Open { .. })));
+
+        let missing_database = get_entries(PathBuf::from("tests/test-files/missing.csv"), None);
+        assert!(matches!(missing_database, Err(Error::Open { .. })));
+
+        let not_a_database = get_entries(PathBuf::from("tests/test-files/report.txt"), None);
+        assert!(matches!(not_a_database, Err(Error::UnsupportedFileType(_))));
+
+        let missing_hash_file = check_database_offline(
+            PathBuf::from("tests/test-files/missing_hashes.txt"),
+            &[],
+            &VisibilityPreference::Hide,
+            BreachedPasswordState::Sha1,
+        );
+        assert!(matches!(missing_hash_file, Err(Error::Open { .. })));
+    }
+
+    // Exit codes of the medic binary itself
+    fn run_medic(args: &[&str]) -> Option<i32> {
+        std::process::Command::new(env!("CARGO_BIN_EXE_medic"))
+            .args(args)
+            .output()
+            .unwrap()
+            .status
+            .code()
+    }
+
+    #[test]
+    fn old_reused_passwords_are_high_priority_without_the_duplicate_check() {
+        let stale_priorities = |databases: &[&str]| -> Vec<bool> {
+            let output = std::process::Command::new(env!("CARGO_BIN_EXE_medic"))
+                .args(["--max-age", "1", "--format", "json"])
+                .args(databases)
+                .output()
+                .unwrap();
+            let report = Report::from_json(&String::from_utf8(output.stdout).unwrap()).unwrap();
+            assert!(!report.has_run(Check::Duplicate));
+            report
+                .findings_for(Check::Stale)
+                .map(|finding| finding.high_priority)
+                .collect()
+        };
+        let csv_export = "tests/test-files/csv_exports/keepassxc_csv_export.csv";
+        assert_eq!(stale_priorities(&[csv_export]), [false, false, false]);
+        // Every password in this export is re-used in the other one, which has no
+        // modification times of its own
+        assert_eq!(
+            stale_priorities(&[csv_export, "tests/test-files/csv_exports/csv_export.csv"]),
+            [true, true, true]
+        );
+    }
+
+    #[test]
+    fn key_files_go_with_the_database_they_name() {
+        let csv_export = "tests/test-files/csv_exports/keepassxc_csv_export.csv";
+        let other_csv_export = "tests/test-files/csv_exports/csv_export.csv";
+        let keyfile = "tests/test-files/v4/version_4_test_db_keyfile";
+        let for_second = format!("{}={}", other_csv_export, keyfile);
+        // A key file for just the second database. The two exports share passwords.
+        assert_eq!(
+            run_medic(&["-d", "-k", &for_second, csv_export, other_csv_export]),
+            Some(1)
+        );
+        // With one database, there's no need to say which it's for
+        assert_eq!(run_medic(&["-d", "-k", keyfile, csv_export]), Some(0));
+        // ...but with several, there is
+        assert_eq!(
+            run_medic(&["-d", "-k", keyfile, csv_export, other_csv_export]),
+            Some(2)
+        );
+        let for_missing = format!("missing.kdbx={}", keyfile);
+        assert_eq!(run_medic(&["-d", "-k", &for_missing, csv_export]), Some(2));
+        assert_eq!(
+            run_medic(&[
+                "-d",
+                "-k",
+                &for_second,
+                "-k",
+                &for_second,
+                csv_export,
+                other_csv_export
+            ]),
+            Some(2)
+        );
+
+        // A key file whose name has a "=" in it still works on its own
+        let dir = std::env::temp_dir().join(format!("medic-keyfile-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let odd_keyfile = dir.join("a=b.key");
+        std::fs::copy(keyfile, &odd_keyfile).unwrap();
+        let exit_code = run_medic(&["-d", "-k", odd_keyfile.to_str().unwrap(), csv_export]);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(exit_code, Some(0));
+    }
+
+    #[test]
+    fn databases_can_be_unlocked_with_a_password_file() {
+        let dir = std::env::temp_dir().join(format!("medic-password-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let password_file = dir.join("password.txt");
+        std::fs::write(&password_file, "password\n").unwrap();
+        let wrong_password_file = dir.join("wrong-password.txt");
+        std::fs::write(&wrong_password_file, "hunter2\n").unwrap();
+        let password_file = password_file.to_str().unwrap();
+        let database = "tests/test-files/v4/test_db_no_keyfile_v4.kdbx";
+        let database_with_keyfile = "tests/test-files/v4/version_4_test_db.kdbx";
+        let keyfile = "tests/test-files/v4/version_4_test_db_keyfile";
+        // Nothing is read from stdin, so medic can't have asked for a password
+        let run = |args: &[&str]| {
+            std::process::Command::new(env!("CARGO_BIN_EXE_medic"))
+                .args(args)
+                .stdin(std::process::Stdio::null())
+                .output()
+                .unwrap()
+                .status
+                .code()
+        };
+
+        assert_eq!(
+            run(&["-d", "--password-file", password_file, database]),
+            Some(1)
+        );
+        assert_eq!(
+            run(&[
+                "-d",
+                "--password-file",
+                &format!("{}={}", database, password_file),
+                "--password-file",
+                &format!("{}={}", database_with_keyfile, password_file),
+                "-k",
+                &format!("{}={}", database_with_keyfile, keyfile),
+                database,
+                database_with_keyfile,
+            ]),
+            Some(1)
+        );
+        assert_eq!(
+            run(&[
+                "-d",
+                "--password-file",
+                password_file,
+                database,
+                database_with_keyfile
+            ]),
+            Some(2)
+        );
+        assert_eq!(
+            run(&[
+                "-d",
+                "--password-file",
+                wrong_password_file.to_str().unwrap(),
+                database
+            ]),
+            Some(3)
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn exits_with_documented_exit_codes() {
+        let csv_export = "tests/test-files/csv_exports/keepassxc_csv_export.csv";
+        // No duplicates in this export
+        assert_eq!(run_medic(&["-d", csv_export]), Some(0));
+        // ...but it does have weak passwords
+        assert_eq!(run_medic(&["-dw", csv_export]), Some(1));
+        assert_eq!(
+            run_medic(&["-dw", "--fail-on", "breached,duplicate", csv_export]),
+            Some(0)
+        );
+        assert_eq!(run_medic(&[csv_export]), Some(2));
+        assert_eq!(
+            run_medic(&["-d", "--fail-on", "nonsense", csv_export]),
+            Some(2)
+        );
+        assert_eq!(run_medic(&["-d", "tests/test-files/missing.csv"]), Some(4));
+    }
+
+    #[test]
+    fn json_report_round_trips_and_never_includes_passwords() {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_medic"))
+            .args([
+                "-dw",
+                "--format",
+                "json",
+                "tests/test-files/csv_exports/keepassxc_csv_export.csv",
+            ])
+            .output()
+            .unwrap();
+        let json = String::from_utf8(output.stdout).unwrap();
+        for password in ["p4ssword", "twitterpassword"] {
+            assert!(!json.contains(password));
+        }
+        let report: report::Report = serde_json::from_str(&json).unwrap();
+        assert_eq!(report.schema_version, report::SCHEMA_VERSION);
+        assert_eq!(report.entries_checked, 3);
+        let weak_titles: Vec<&str> = report
+            .findings
+            .iter()
+            .filter(|finding| finding.check == Check::Weak)
+            .map(|finding| finding.entry.title.as_str())
+            .collect();
+        assert_eq!(weak_titles, ["GitHub", "Twitter"]);
+        assert_eq!(report.findings[0].entry.group, "Root");
+    }
+
+    #[test]
+    fn can_write_report_as_csv_and_markdown() {
+        let entries = make_test_entries_from_keepassxc_csv_export();
+        let mut report = report::Report::new(entries.len());
+        report.add_weak(&find_weak_passwords(&entries, DEFAULT_MIN_SCORE, &[], &[]).unwrap());
+
+        let csv = report.to_csv().unwrap();
+        let mut rdr = csv::Reader::from_reader(csv.as_bytes());
+        let rows: Vec<csv::StringRecord> = rdr.records().map(|row| row.unwrap()).collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(&rows[0][0], "weak");
+        assert_eq!(&rows[0][1], "GitHub");
+        assert_eq!(&rows[0][8], "prod;work");
+
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("## Weak passwords (2)"));
+        assert!(markdown.contains("| GitHub | testuser | github.com | Root | score 0/4;"));
+        for password in ["p4ssword", "twitterpassword"] {
+            assert!(!csv.contains(password));
+            assert!(!markdown.contains(password));
+        }
+    }
+
+    #[test]
+    fn can_write_self_contained_html_report() {
+        let entries = make_test_entries_from_keepassxc_csv_export();
+        let mut report = report::Report::new(entries.len());
+        report.add_weak(&find_weak_passwords(&entries, DEFAULT_MIN_SCORE, &[], &[]).unwrap());
+        // 2 of the 3 entries have weak passwords, each costing half of its third of the score
+        assert_eq!(report.health_score(), 67);
+
+        let html = report.to_html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h2>Weak passwords (2)</h2>"));
+        assert!(html.contains("don&#39;t help very much"));
+        // No external stylesheets, scripts or images
+        for external in ["<link", "src=", "http://", "https://"] {
+            assert!(!html.contains(external));
+        }
+        for password in ["p4ssword", "twitterpassword"] {
+            assert!(!html.contains(password));
+        }
+    }
+
+    #[test]
+    fn can_build_a_report_without_printing_anything() {
+        // Entries whose password can't be read are returned, rather than printed
+        let unreadable = entries::read_keepass_db(
+            PathBuf::from("tests/test-files/v4/test_db_no_keyfile_v4.kdbx"),
+            "password".to_string(),
+            None,
+        )
+        .unwrap()
+        .unreadable;
+        let unreadable_titles: Vec<&str> = unreadable
+            .iter()
+            .map(|entry| entry.title.as_str())
+            .collect();
+        assert_eq!(
+            unreadable_titles,
+            ["Bob's phone number", "Alice's phone number"]
+        );
+
+        let entries = make_test_entries_from_keepass_4_database_requiring_keyfile();
+        let mut report = Report::new(entries.len());
+        report.add_duplicates(&make_digest_map(&entries).unwrap());
+        let breached_entries = check_database_offline(
+            PathBuf::from("tests/test-files/cleartext_passwords.txt"),
+            &entries,
+            &VisibilityPreference::Hide,
+            BreachedPasswordState::Clear,
+        )
+        .unwrap();
+        report.add_breached(&breached_entries);
+        report.add_weak(&[]);
+
+        let summary = report.summary();
+        assert_eq!(summary.entries_checked, entries.len());
+        assert_eq!(summary.counts[&Check::Duplicate], 4);
+        assert_eq!(summary.counts[&Check::Breached], 4);
+        assert_eq!(summary.counts[&Check::Weak], 0);
+        assert!(!summary.counts.contains_key(&Check::Stale));
+        assert_eq!(summary.findings, 8);
+        assert_eq!(summary.entries_with_findings, 8);
+        assert!(report.has_run(Check::Weak));
+        assert!(!report.has_run(Check::Stale));
+    }
+
+    fn json_summary(args: &[&str]) -> serde_json::Value {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_medic"))
+            .args(args)
+            .args(["--format", "json"])
+            .output()
+            .unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        report["summary"].clone()
+    }
+
+    #[test]
+    fn entries_that_look_the_same_are_still_counted_separately() {
+        let summary = json_summary(&["-dw", "tests/test-files/csv_exports/identical_rows.csv"]);
+        assert_eq!(summary["counts"]["duplicate"], 2);
+        assert_eq!(summary["counts"]["weak"], 2);
+        assert_eq!(summary["entries_with_findings"], 2);
+        // Both re-use a password, which costs each of them most of its half of the score
+        assert_eq!(summary["health_score"], 20);
+    }
+
+    #[test]
+    fn entries_on_several_breach_lists_are_reported_once() {
+        let dir = std::env::temp_dir().join(format!("medic-lists-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let passwords_file = dir.join("passwords.txt");
+        let hash_file = dir.join("hashes.txt");
+        std::fs::write(&passwords_file, "p4ssword\n").unwrap();
+        let digest = sha1_smol::Sha1::from("p4ssword")
+            .digest()
+            .to_string()
+            .to_uppercase();
+        std::fs::write(&hash_file, format!("{}:1\n", digest)).unwrap();
+
+        let summary = json_summary(&[
+            "-p",
+            passwords_file.to_str().unwrap(),
+            "-a",
+            hash_file.to_str().unwrap(),
+            "tests/test-files/csv_exports/keepassxc_csv_export.csv",
+        ]);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(summary["counts"]["breached"], 1);
+    }
+
+    #[test]
+    fn health_score_weighs_findings_by_severity_and_tags() {
+        let entries = make_test_entries_from_keepassxc_csv_export();
+        let github: Vec<Entry> = entries
+            .iter()
+            .filter(|entry| entry.title == "GitHub")
+            .cloned()
+            .collect();
+
+        let mut clean = Report::new(entries.len());
+        clean.add_breached(&[]);
+        assert_eq!(clean.summary().health_score, 100);
+
+        let mut weak = Report::new(entries.len());
+        weak.add_weak(&find_weak_passwords(&github, DEFAULT_MIN_SCORE, &[], &[]).unwrap());
+        let mut breached = Report::new(entries.len());
+        breached.add_breached(&github);
+        // Breached passwords are worse than weak ones
+        assert_eq!(weak.health_score(), 83);
+        assert_eq!(breached.health_score(), 67);
+
+        // GitHub is tagged "prod", so weighting that tag makes its breach count for more
+        let tag_weights: std::collections::HashMap<String, u32> =
+            [("prod".to_string(), 3)].into_iter().collect();
+        breached.set_tag_weights(&entries, &tag_weights);
+        assert_eq!(breached.health_score(), 40);
+
+        let summary = breached.summary();
+        assert_eq!(summary.counts[&Check::Breached], 1);
+        assert_eq!(summary.health_score, 40);
+        let json: serde_json::Value = serde_json::from_str(&breached.to_json().unwrap()).unwrap();
+        assert_eq!(json["summary"]["health_score"], 40);
+
+        // A custom field checked as a secret counts towards its entry, not as another entry
+        let mut github_token = github[0].clone();
+        github_token.field = Some("API token".to_string());
+        let mut with_field = Report::new(entries.len());
+        with_field.add_breached(&[github[0].clone(), github_token]);
+        assert_eq!(with_field.summary().entries_with_findings, 1);
+        assert_eq!(with_field.health_score(), 67);
+
+        // The total weight is only reported when tags are weighted
+        let csv_export = "tests/test-files/csv_exports/keepassxc_csv_export.csv";
+        let report_json = |args: &[&str]| -> serde_json::Value {
+            let output = std::process::Command::new(env!("CARGO_BIN_EXE_medic"))
+                .args(args)
+                .args(["--format", "json", csv_export])
+                .output()
+                .unwrap();
+            serde_json::from_slice(&output.stdout).unwrap()
+        };
+        assert!(report_json(&["-w"]).get("entries_weight").is_none());
+        assert_eq!(
+            report_json(&["-w", "--tag-weight", "prod=3"])["entries_weight"],
+            5
+        );
+    }
+
+    #[test]
+    fn can_diff_two_reports() {
+        let entries = make_test_entries_from_keepassxc_csv_export();
+        let mut old = Report::new(entries.len());
+        old.add_weak(&find_weak_passwords(&entries, DEFAULT_MIN_SCORE, &[], &[]).unwrap());
+        // Reports only ever reach a diff as JSON
+        let old = Report::from_json(&old.to_json().unwrap()).unwrap();
+
+        // Since then, GitHub got a strong password, but twitch was breached
+        let mut fixed_entries = entries.clone();
+        fixed_entries[0].pass = "overstock repair landmine duration cushy swell".to_string();
+        let mut new = Report::new(entries.len());
+        new.add_weak(&find_weak_passwords(&fixed_entries, DEFAULT_MIN_SCORE, &[], &[]).unwrap());
+        new.add_breached(&entries[2..]);
+
+        let diff = diff::ReportDiff::new(&old, &new);
+        let titles = |findings: &[report::Finding]| -> Vec<String> {
+            findings
+                .iter()
+                .map(|finding| finding.entry.title.clone())
+                .collect()
+        };
+        assert_eq!(titles(&diff.resolved), ["GitHub"]);
+        assert_eq!(titles(&diff.new), ["twitch"]);
+        assert_eq!(titles(&diff.unchanged), ["Twitter"]);
+        assert!(diff.new_health_score < diff.old_health_score);
+        assert!(!diff.to_json().unwrap().contains("twitterpassword"));
+    }
+
+    #[test]
+    fn can_diff_saved_reports_from_the_command_line() {
+        let csv_export = "tests/test-files/csv_exports/keepassxc_csv_export.csv";
+        let dir = std::env::temp_dir().join(format!("medic-diff-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let old_report = dir.join("old.json");
+        let new_report = dir.join("new.json");
+        for (report_path, args) in [
+            (&old_report, ["-d", "--format", "json"]),
+            (&new_report, ["-w", "--format", "json"]),
+        ] {
+            let output = std::process::Command::new(env!("CARGO_BIN_EXE_medic"))
+                .args(args)
+                .arg(csv_export)
+                .output()
+                .unwrap();
+            std::fs::write(report_path, output.stdout).unwrap();
+        }
+
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_medic"))
+            .args(["--diff", "--format", "json"])
+            .arg(&old_report)
+            .arg(&new_report)
+            .output()
+            .unwrap();
+        // The weak passwords only show up in the new report
+        assert_eq!(output.status.code(), Some(1));
+        let diff: diff::ReportDiff = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(diff.new.len(), 2);
+        assert!(diff.resolved.is_empty());
+
+        assert_eq!(run_medic(&["--diff", csv_export]), Some(2));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn can_set_weak_password_threshold_and_report_crack_times() {
+        let entries = make_test_entries_from_keepassxc_csv_export();
+        assert_eq!(
+            find_weak_passwords(&entries, DEFAULT_MIN_SCORE, &[], &[])
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(
+            find_weak_passwords(&entries, 0, &[], &[])
+                .unwrap()
+                .is_empty()
+        );
+
+        // Only GitHub's "p4ssword" scores 0
+        let weakest = find_weak_passwords(&entries, 1, &[], &[]).unwrap();
+        assert_eq!(weakest.len(), 1);
+        assert_eq!(weakest[0].entry.title, "GitHub");
+        assert!(weakest[0].guesses > 0);
+        assert!(weakest[0].guesses_log10 < 3.0);
+        let crack_times = &weakest[0].crack_times;
+        assert!(crack_times.offline_fast_hash_seconds < crack_times.online_throttled_seconds);
+        assert_eq!(crack_times.offline_fast_hash, "less than a second");
+
+        let mut report = Report::new(entries.len());
+        report.add_weak(&weakest);
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        let finding = &json["findings"][0];
+        assert_eq!(finding["guesses"], weakest[0].guesses);
+        assert_eq!(
+            finding["crack_times"]["online_throttled"],
+            crack_times.online_throttled
+        );
+    }
+
+    #[test]
+    fn weak_password_check_uses_domain_group_email_and_context_words() {
+        assert_eq!(
+            context::registrable_domain("accounts.northwindbank.co.uk"),
+            Some("northwindbank.co.uk".to_string())
+        );
+        assert_eq!(context::registrable_domain("co.uk"), None);
+        assert_eq!(context::registrable_domain("192.168.1.1"), None);
+        assert_eq!(
+            context::host("https://jane@www.Example.com:8443/login?next=/"),
+            Some("example.com".to_string())
+        );
+
+        let context_words =
+            context::read_context_words(PathBuf::from("tests/test-files/context_words.txt"))
+                .unwrap();
+        assert_eq!(context_words, ["quuxly", "Northwind Traders"]);
+
+        let entry = Entry {
+            title: "Bank".to_string(),
+            username: "jane.doe+bank@mail.example.org".to_string(),
+            url: "https://secure.northwindbank.co.uk/login".to_string(),
+            group: "Root/Banking".to_string(),
+            ..Default::default()
+        };
+        let user_inputs = context::user_inputs(&entry, &context_words);
+        for input in [
+            "northwindbank",
+            "northwindbank.co.uk",
+            "secure.northwindbank.co.uk",
+            "banking",
+            "jane.doe+bank",
+            "jane",
+            "doe",
+            "example",
+            "quuxly",
+            "northwind traders",
+        ] {
+            assert!(user_inputs.contains(&input.to_string()), "{}", input);
+        }
+
+        // Strong passwords, unless you know where they're used
+        let with_password = |pass: &str| Entry {
+            pass: pass.to_string(),
+            ..entry.clone()
+        };
+        let from_domain = [with_password("Northwindbank2024")];
+        assert_eq!(
+            find_weak_passwords(&from_domain, 4, &[], &[])
+                .unwrap()
+                .len(),
+            1
+        );
+        // Nothing about this entry hints at "quuxly", except the context words
+        let from_context_words = [Entry {
+            pass: "quuxlyBanking!".to_string(),
+            ..Default::default()
+        }];
+        assert!(
+            find_weak_passwords(&from_context_words, 4, &[], &[])
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            find_weak_passwords(&from_context_words, 4, &context_words, &[])
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Context words only mean something to the weak password check
+        assert_eq!(
+            run_medic(&[
+                "-d",
+                "--context-words",
+                "tests/test-files/context_words.txt",
+                "tests/test-files/csv_exports/keepassxc_csv_export.csv",
+            ]),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn can_check_passwords_against_password_policy() {
+        let policy = policy::read_policy(PathBuf::from("tests/test-files/policy.toml")).unwrap();
+        let entries = make_test_entries_from_keepassxc_csv_export();
+        let violations = policy::find_policy_violations(&entries, &policy);
+        let violations: Vec<(&str, Vec<String>)> = violations
+            .iter()
+            .map(|(entry, violations)| (entry.title.as_str(), violations.clone()))
+            .collect();
+        // twitch is in Root/entertainment, which doesn't require uppercase letters or digits
+        assert_eq!(
+            violations,
+            [
+                (
+                    "GitHub",
+                    vec![
+                        "shorter than 12 characters".to_string(),
+                        "has no uppercase letter".to_string()
+                    ]
+                ),
+                (
+                    "Twitter",
+                    vec![
+                        "has no uppercase letter".to_string(),
+                        "has no digit".to_string(),
+                        "contains \"password\"".to_string()
+                    ]
+                ),
+            ]
+        );
+
+        // Group overrides also apply to groups inside the group
+        let rules = policy.rules_for("Root/entertainment/games");
+        assert_eq!(rules.require_digit, Some(false));
+        assert_eq!(rules.min_length, Some(12));
+
+        let entry = Entry {
+            username: "Jane".to_string(),
+            pass: "Xjaneee-Rocks-42".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            policy::policy_violations(&entry, &policy.rules_for("Root")),
+            [
+                "contains the username",
+                "repeats a character more than 2 times in a row"
+            ]
+        );
+
+        let typo = policy::read_policy(PathBuf::from("tests/test-files/policy_with_typo.toml"));
+        assert!(matches!(typo, Err(Error::Parse(_))));
+
+        // The typo is reported before the database's password is asked for
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_medic"))
+            .args([
+                "-w",
+                "--policy",
+                "tests/test-files/policy_with_typo.toml",
+                "tests/test-files/v4/test_db_no_keyfile_v4.kdbx",
+            ])
+            .stdin(std::process::Stdio::null())
+            .output()
+            .unwrap();
+        assert!(
+            String::from_utf8(output.stderr)
+                .unwrap()
+                .contains("min_lenght")
+        );
+    }
+
+    #[test]
+    fn passwords_with_banned_words_are_weak_whatever_their_score() {
+        let banned_words =
+            banned::read_banned_words(PathBuf::from("tests/test-files/banned_words.txt")).unwrap();
+        assert_eq!(banned_words, ["northwind", "quokka"]);
+
+        assert_eq!(
+            banned::banned_words_in("Harbour-N0RTHW!ND-88", &banned_words),
+            ["northwind"]
+        );
+        assert_eq!(
+            banned::banned_words_in("qu0kk4s and N0rthw1nd", &banned_words),
+            ["northwind", "quokka"]
+        );
+        assert!(banned::banned_words_in("North-wind", &banned_words).is_empty());
+
+        let entries = [Entry {
+            title: "Intranet".to_string(),
+            pass: "velvet crane harbour N0rthw1nd toaster".to_string(),
+            ..Default::default()
+        }];
+        // zxcvbn alone thinks this is a great password
+        assert!(
+            find_weak_passwords(&entries, DEFAULT_MIN_SCORE, &[], &[])
+                .unwrap()
+                .is_empty()
+        );
+        let weak_passwords =
+            find_weak_passwords(&entries, DEFAULT_MIN_SCORE, &[], &banned_words).unwrap();
+        assert_eq!(weak_passwords.len(), 1);
+        assert_eq!(weak_passwords[0].score, 4);
+        assert_eq!(weak_passwords[0].banned_words, ["northwind"]);
+
+        let mut report = Report::new(entries.len());
+        report.add_weak(&weak_passwords);
+        let finding = report.findings_for(Check::Weak).next().unwrap();
+        assert_eq!(
+            finding.detail.as_deref(),
+            Some("contains the banned word \"northwind\"")
+        );
+        assert!(!report.to_json().unwrap().contains("N0rthw1nd"));
+
+        // Banned words only mean something to the weak password check
+        assert_eq!(
+            run_medic(&[
+                "-d",
+                "--banned-words",
+                "tests/test-files/banned_words.txt",
+                "tests/test-files/csv_exports/keepassxc_csv_export.csv",
+            ]),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn can_find_clusters_of_similar_passwords() {
+        let entries = [
+            make_test_entry("Gym", "", "Summer2023!"),
+            make_test_login("GitHub", "", "https://github.com", "GitHub-Tr0ub4dor&3"),
+            make_test_entry("Bank", "", "Summer2024!"),
+            make_test_entry("Router", "", "1234"),
+            make_test_login(
+                "Twitter",
+                "",
+                "https://twitter.com/login",
+                "twitter-Tr0ub4dor&3",
+            ),
+            make_test_entry("Phone", "", "5678"),
+            make_test_entry("Forum", "", "hunter2horse"),
+            make_test_entry("Wiki", "", "correct horse battery staple"),
+            make_test_entry("Chat", "", "hunter2horses"),
+            make_test_entry("Shop", "", "Summer2023!"),
+        ];
+        let clusters = similar::find_similar_passwords(&entries);
+        let titles: Vec<Vec<&str>> = clusters
+            .iter()
+            .map(|cluster| cluster.iter().map(|entry| entry.title.as_str()).collect())
+            .collect();
+        assert_eq!(
+            titles,
+            [
+                vec!["Gym", "Bank", "Shop"],
+                vec!["GitHub", "Twitter"],
+                vec!["Forum", "Chat"]
+            ]
+        );
+
+        // Identical passwords alone are duplicates, not similar ones
+        assert!(
+            similar::find_similar_passwords(&[entries[0].clone(), entries[9].clone()]).is_empty()
+        );
+
+        let mut report = Report::new(entries.len());
+        report.add_similar(&clusters);
+        assert_eq!(report.count(Check::Similar), 7);
+        let finding = report.findings_for(Check::Similar).next().unwrap();
+        assert_eq!(finding.describe(), "similar group 1 (3 entries)");
+
+        // The order of the entries makes no difference
+        let mut reversed = entries.to_vec();
+        reversed.reverse();
+        let mut reversed_report = Report::new(reversed.len());
+        reversed_report.add_similar(&similar::find_similar_passwords(&reversed));
+        assert_eq!(reversed_report, report);
+        let mostly_digits = [
+            make_test_entry("One", "", "abc12345"),
+            make_test_entry("Two", "", "abcd2345"),
+        ];
+        assert!(similar::find_similar_passwords(&mostly_digits).is_empty());
+        let mut mostly_digits = mostly_digits.to_vec();
+        mostly_digits.reverse();
+        assert!(similar::find_similar_passwords(&mostly_digits).is_empty());
+    }
+
+    #[test]
+    fn allowlisted_findings_are_listed_separately_with_their_reason() {
+        let allowlist =
+            allowlist::read_allowlist(PathBuf::from("tests/test-files/allowlist.toml")).unwrap();
+        let entries = [
+            make_test_entry(
+                "Okta (Zoom)",
+                "0f6b6c2e-3a4d-4b5c-8d9e-1a2b3c4d5e6f",
+                "sso-password",
+            ),
+            make_test_login("Okta (Slack)", "jane", "", "sso-password"),
+            make_test_entry("Printer", "", "printer"),
+            make_test_entry("Fax", "", "printer"),
+            make_test_entry("Staging router", "", "admin"),
+        ];
+        let mut report = Report::new(entries.len());
+        report.add_duplicates(&make_digest_map(&entries).unwrap());
+        report.add_weak(&find_weak_passwords(&entries[4..], DEFAULT_MIN_SCORE, &[], &[]).unwrap());
+        report.add_breached(&entries[2..3]);
+        report.add_policy_violations(&[(entries[4].clone(), vec!["too short".to_string()])]);
+        report.apply_allowlist(&allowlist);
+
+        let findings: Vec<(Check, &str)> = report
+            .findings
+            .iter()
+            .map(|finding| (finding.check, finding.entry.title.as_str()))
+            .collect();
+        // The fax isn't allowed to share the printer's password, so neither is reported as
+        // allowed. Only weak findings are allowed for the router.
+        assert!(findings.contains(&(Check::Duplicate, "Printer")));
+        assert!(findings.contains(&(Check::Duplicate, "Fax")));
+        assert!(findings.contains(&(Check::Policy, "Staging router")));
+        assert_eq!(findings.len(), 3);
+
+        let suppressed: Vec<(Check, &str, &str)> = report
+            .suppressed
+            .iter()
+            .map(|suppressed| {
+                (
+                    suppressed.finding.check,
+                    suppressed.finding.entry.title.as_str(),
+                    suppressed.reason.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(suppressed.len(), 4);
+        assert!(suppressed.contains(&(
+            Check::Duplicate,
+            "Okta (Zoom)",
+            "Okta SSO, stored once per app that uses it"
+        )));
+        assert!(suppressed.contains(&(
+            Check::Weak,
+            "Staging router",
+            "Only reachable from the lab network"
+        )));
+        assert!(suppressed.contains(&(
+            Check::Breached,
+            "Printer",
+            "Shares its password with the fax on purpose"
+        )));
+        assert_eq!(report.count(Check::Weak), 0);
+        assert!(report.to_markdown().contains("## Allowed findings (4)"));
+        assert_eq!(
+            Report::from_json(&report.to_json().unwrap()).unwrap(),
+            report
+        );
+
+        let without_reason = allowlist::read_allowlist(PathBuf::from(
+            "tests/test-files/allowlist_without_reason.toml",
+        ));
+        assert!(matches!(without_reason, Err(Error::Parse(_))));
+        let with_empty_uuid = allowlist::read_allowlist(PathBuf::from(
+            "tests/test-files/allowlist_with_empty_uuid.toml",
+        ));
+        assert!(matches!(with_empty_uuid, Err(Error::Parse(_))));
+
+        // Built by hand, an empty UUID still doesn't match entries that have none
+        let empty_uuid = allowlist::Allowlist {
+            allow: vec![allowlist::AllowRule {
+                uuid: Some(String::new()),
+                reason: "Matches nothing".to_string(),
+                ..Default::default()
+            }],
+        };
+        let mut report = Report::new(entries.len());
+        report.add_duplicates(&make_digest_map(&entries).unwrap());
+        report.apply_allowlist(&empty_uuid);
+        assert!(report.suppressed.is_empty());
+    }
+
+    #[test]
+    fn duplicates_within_one_account_are_told_apart_from_reuse_across_sites() {
+        let entries = [
+            make_test_login("Gmail", "Jane", "https://mail.google.com", "correct horse"),
+            make_test_login(
+                "Google",
+                "jane",
+                "https://accounts.google.com/signin",
+                "correct horse",
+            ),
+            make_test_login("Bank", "jane", "https://mybank.co.uk", "battery staple"),
+            make_test_login(
+                "Forum",
+                "jane",
+                "https://forum.example.com",
+                "battery staple",
+            ),
+        ];
+        assert_eq!(
+            shared_account_domain(&entries[..2]),
+            Some("google.com".to_string())
+        );
+        assert_eq!(shared_account_domain(&entries[2..]), None);
+
+        let mut report = Report::new(entries.len());
+        report.add_duplicates(&make_digest_map(&entries).unwrap());
+        let groups: Vec<(usize, &str, bool)> = report
+            .findings_for(Check::Duplicate)
+            .map(|finding| {
+                (
+                    finding.duplicate_group.unwrap(),
+                    finding.entry.title.as_str(),
+                    finding.same_account,
+                )
+            })
+            .collect();
+        // Re-use across sites comes first
+        assert_eq!(groups[0], (1, "Bank", false));
+        assert_eq!(groups[2], (2, "Gmail", true));
+        assert_eq!(
+            report
+                .findings_for(Check::Duplicate)
+                .nth(3)
+                .unwrap()
+                .describe(),
+            "duplicate group 2 (2 entries); same account on google.com"
+        );
+
+        // ...and counts for more against the health score
+        let mut cross_site = Report::new(entries.len());
+        cross_site.add_duplicates(&make_digest_map(&entries[2..]).unwrap());
+        let mut same_account = Report::new(entries.len());
+        same_account.add_duplicates(&make_digest_map(&entries[..2]).unwrap());
+        assert!(cross_site.health_score() < same_account.health_score());
+    }
+
+    // If the report's wording changes on purpose, regenerate the golden file with:
+    // cargo run -- -dw --similar tests/test-files/csv_exports/golden_export.csv > tests/test-files/golden-report.txt
+    #[test]
+    fn text_report_matches_golden_file() {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_medic"))
+            .args([
+                "-dw",
+                "--similar",
+                "tests/test-files/csv_exports/golden_export.csv",
+            ])
+            .output()
+            .unwrap();
+        let golden = std::fs::read_to_string("tests/test-files/golden-report.txt").unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), golden);
+    }
+
+    #[test]
+    fn can_find_passwords_built_from_their_entry_details() {
+        let found = |title: &str, username: &str, url: &str, pass: &str| {
+            context::entry_details_in_password(&make_test_login(title, username, url, pass))
+        };
+        assert_eq!(
+            found("GitHub", "jane", "https://github.com", "github-JANE"),
+            [
+                "contains the username \"jane\"",
+                "contains the title \"GitHub\""
+            ]
+        );
+        assert_eq!(
+            found("Shop", "jane.doe@example.com", "", "J4n3.D0e!2024"),
+            ["contains the email address's local part \"jane.doe\""]
+        );
+        assert_eq!(
+            found("Bank of America", "", "", "BankOfAmerica#1"),
+            ["contains the title \"Bank of America\""]
+        );
+        assert_eq!(
+            found(
+                "Bank",
+                "",
+                "https://secure.northwindbank.co.uk/login",
+                "S3cure-Northwind-99"
+            ),
+            ["contains part of the URL \"secure\""]
+        );
+        assert_eq!(
+            found(
+                "Savings",
+                "",
+                "https://northwind-bank.co.uk",
+                "n0rthw1nd-b4nk"
+            ),
+            ["contains part of the URL \"northwind-bank\""]
+        );
+        // Too short to count, and IP addresses aren't words
+        assert!(found("Router", "jo", "192.168.1.1", "jo192168").is_empty());
+
+        // zxcvbn thinks this one is fine, but it's still flagged
+        let entries = [make_test_login(
+            "Intranet",
+            "jane",
+            "",
+            "velvet crane harbour intranet toaster",
+        )];
+        let found = context::find_entry_details_in_passwords(&entries);
+        let mut report = Report::new(entries.len());
+        report.add_entry_details_in_passwords(&found);
+        assert_eq!(
+            report
+                .findings_for(Check::Context)
+                .next()
+                .unwrap()
+                .detail
+                .as_deref(),
+            Some("contains the title \"Intranet\"")
+        );
+    }
+
+    #[test]
+    fn can_find_families_of_passwords_made_from_one_template() {
+        let entries = [
+            make_test_entry("GitHub", "", "Base!GitHub#2024"),
+            make_test_entry("Mail", "", "xq7-Winter99!"),
+            make_test_entry("Twitter", "", "Base!Twitter#2024"),
+            make_test_entry("Bank", "", "Base!Bank#2024"),
+            make_test_entry("Savings", "", "Base!Bank#2024"),
+            make_test_entry("Forum", "", "Hello123"),
+            make_test_entry("Chat", "", "Lm2-Winter99!"),
+            make_test_entry("Wiki", "", "Hello456"),
+            make_test_entry("Shop", "", "p0p-Winter99!"),
+        ];
+        let families = patterns::find_pattern_families(&entries, 5);
+        let families: Vec<(Vec<&str>, String)> = families
+            .iter()
+            .map(|family| {
+                let mut titles: Vec<&str> = family
+                    .entries
+                    .iter()
+                    .map(|entry| entry.title.as_str())
+                    .collect();
+                titles.sort();
+                (titles, family.describe())
+            })
+            .collect();
+        assert_eq!(families.len(), 2);
+        assert!(families.contains(&(
+            vec!["Bank", "GitHub", "Savings", "Twitter"],
+            "start with the same 5 characters and end with the same 5".to_string()
+        )));
+        assert!(families.contains(&(
+            vec!["Chat", "Mail", "Shop"],
+            "end with the same 10 characters".to_string()
+        )));
+        assert!(patterns::find_pattern_families(&entries, 11).is_empty());
+
+        let mut report = Report::new(entries.len());
+        report.add_pattern_families(&patterns::find_pattern_families(&entries, 5));
+        let finding = report.findings_for(Check::Pattern).next().unwrap();
+        assert_eq!(finding.entry.title, "Bank");
+        assert_eq!(
+            finding.describe(),
+            "pattern family 1 (4 entries); start with the same 5 characters and end with the same 5"
+        );
+        // The shared characters are part of each password, so they're never written out
+        let json = report.to_json().unwrap();
+        assert!(!json.contains("Base!") && !json.contains("Winter99"));
+    }
 }